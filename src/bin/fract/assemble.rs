@@ -0,0 +1,100 @@
+use crate::{EncoderArgs, progress_bar};
+use fract::encoder::{ApngEncoder, Encoder, FrameEncoder, FrameManifest, GifEncoder, read_frame};
+use std::process::ExitCode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Directory the frame ranges were rendered to.
+    input: String,
+
+    /// Output file, either `MP4`, `APNG`/`PNG` or `GIF`.
+    output: String,
+
+    #[command(flatten)]
+    encoder: EncoderArgs,
+}
+
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    let manifest = match FrameManifest::from_dir(&args.input) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            eprintln!("[ERROR] Failed to read {}/frames.toml: {err}", args.input);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let missing = manifest.missing_frames(&args.input);
+    if !missing.is_empty() {
+        eprintln!(
+            "[ERROR] {} of {} frames are missing: {}",
+            missing.len(),
+            manifest.frames,
+            ranges(&missing)
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let format = args.output.to_lowercase();
+    let (w, h, fps) = (manifest.width, manifest.height, manifest.fps);
+    if format.ends_with(".mp4") {
+        let options = args.encoder.options(false);
+        let encoder = Encoder::new(&args.output, args.input.clone(), w, h, fps, &options)?;
+        assemble(&args, &manifest, encoder)
+    } else if format.ends_with(".apng") || format.ends_with(".png") {
+        let encoder = ApngEncoder::new(&args.output, w, h, fps, manifest.frames)?;
+        assemble(&args, &manifest, encoder)
+    } else if format.ends_with(".gif") {
+        let encoder = GifEncoder::new(&args.output, w, h, fps)?;
+        assemble(&args, &manifest, encoder)
+    } else {
+        eprintln!("[ERROR] Invalid video format, expected MP4, APNG or GIF");
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn assemble(
+    args: &Args,
+    manifest: &FrameManifest,
+    mut encoder: impl FrameEncoder,
+) -> std::io::Result<ExitCode> {
+    let bar = progress_bar("frames", manifest.frames);
+
+    for i in 0..manifest.frames {
+        let path = format!("{}/frames/{i}.png", args.input);
+        let frame = read_frame(&path, manifest.width, manifest.height)?;
+        encoder.render_frame(&frame)?;
+        bar.inc(1);
+    }
+    bar.finish();
+
+    encoder.finish()?;
+    println!(
+        "[LOG] Wrote {} bytes to {}",
+        std::fs::metadata(&args.output)?.len(),
+        args.output
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Format sorted frame indices as compact ranges, e.g. `3, 7-9`.
+fn ranges(frames: &[usize]) -> String {
+    let mut ranges = Vec::<(usize, usize)>::new();
+    for &i in frames {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == i => *end = i,
+            _ => ranges.push((i, i)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                format!("{start}")
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,13 +1,13 @@
-use clap::Parser;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::{
+    process::ExitCode,
+    sync::atomic::{AtomicU32, Ordering},
+};
 use tint::Srgb;
 
-/// Buddhabrot renderer.
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[derive(clap::Args, Debug)]
+pub struct Args {
     /// Output png file.
     output: String,
 
@@ -98,7 +98,8 @@ fn process_channel(
     }
 
     let iterations = channel_config.iterations;
-    let bar = multi.add(progress_bar(size, iterations));
+    let bar = multi.add(crate::progress_bar("cols", size * OVERY as usize / 2));
+    bar.set_prefix(format!("iter={iterations:<8} "));
     let hist = compute_hist(Some(&bar), size, iterations);
 
     let target_stem = format!("{}-{}", size, iterations);
@@ -110,9 +111,16 @@ fn process_channel(
     Ok(hist)
 }
 
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
-    let config = toml::from_str::<Config>(&std::fs::read_to_string(args.config)?).unwrap();
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    let toml = std::fs::read_to_string(&args.config).map_err(|err| {
+        std::io::Error::new(err.kind(), format!("failed to read {}: {err}", args.config))
+    })?;
+    let config = toml::from_str::<Config>(&toml).map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("failed to parse {}: {err}", args.config),
+        )
+    })?;
 
     let multi = MultiProgress::new();
     let (r, (g, b)) = rayon::join(
@@ -155,22 +163,7 @@ fn main() -> std::io::Result<()> {
         false,
     )?;
 
-    Ok(())
-}
-
-fn progress_bar(size: usize, iter: usize) -> ProgressBar {
-    let bar = ProgressBar::no_length();
-    let width = (size * OVERY as usize / 2).to_string().len();
-    bar.set_style(
-        ProgressStyle::with_template(&format!(
-            "[{{elapsed_precise}}] iter={iter:<8} {{bar:40.cyan/blue}} \
-                cols={{pos:>{width}}}/{{len:{}}} eta={{eta_precise}}",
-            width * 2,
-        ))
-        .unwrap()
-        .progress_chars("##-"),
-    );
-    bar
+    Ok(ExitCode::SUCCESS)
 }
 
 const SPANX: f32 = 3.5;
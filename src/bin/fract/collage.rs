@@ -1,12 +1,9 @@
-use clap::Parser;
+use crate::{ConfigArgs, progress_bar};
 use fract::pipeline::Pipeline;
-use indicatif::{ProgressBar, ProgressStyle};
 use std::{process::ExitCode, time::UNIX_EPOCH};
 
-/// Config permutation utility.
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[derive(clap::Args, Debug)]
+pub struct Args {
     /// Output collage file.
     output: Option<String>,
 
@@ -17,22 +14,33 @@ struct Args {
     /// Path to a directory with the config tomls.
     #[arg(short, long)]
     configs: String,
-}
 
-fn main() -> std::io::Result<ExitCode> {
-    let args = Args::parse();
+    /// Profile to apply to every config, e.g. `draft` for `[profile.draft]`.
+    #[arg(long)]
+    profile: Option<String>,
 
-    let count = std::fs::read_dir(&args.configs)?
-        .filter(|p| {
-            p.as_ref()
-                .is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "toml"))
-        })
-        .count();
+    /// Override a field of every config, e.g. `--set iterations=50000`.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+}
 
-    if count == 0 {
-        println!("[ERROR] Directory contains no valid config files");
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    let config_args = ConfigArgs {
+        config: None,
+        profile: args.profile,
+        overrides: args.overrides,
+    };
+
+    let paths: Vec<_> = std::fs::read_dir(&args.configs)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    let count = paths.len();
+    let Some(first) = paths.first() else {
+        eprintln!("[ERROR] Directory contains no valid config files");
         return Ok(ExitCode::FAILURE);
-    }
+    };
 
     let data_root = args.data.unwrap_or_else(|| {
         let current_time = std::time::SystemTime::now()
@@ -43,25 +51,9 @@ fn main() -> std::io::Result<ExitCode> {
     });
     _ = std::fs::create_dir_all(&data_root);
 
-    let bar = ProgressBar::new(count as u64);
-    let images = 4u32.to_string().len();
-    bar.set_style(
-        ProgressStyle::with_template(&format!(
-            "[{{elapsed_precise}}] {{bar:40.cyan/blue}} \
-                frames={{pos:>{images}}}/{{len:{}}} eta={{eta_precise}}",
-            images + 4,
-        ))
-        .unwrap()
-        .progress_chars("##-"),
-    );
-
-    let first = std::fs::read_dir(&args.configs)?
-        .find_map(|p| {
-            p.as_ref()
-                .map(|p| fract::config::from_path(p.path().to_str().unwrap()))
-                .ok()
-        })
-        .expect("directory contains valid config tomls")?;
+    let bar = progress_bar("frames", count);
+
+    let first = config_args.load_path(first)?;
 
     let width = first.width;
     let height = first.height;
@@ -69,25 +61,22 @@ fn main() -> std::io::Result<ExitCode> {
     let mut pipeline = Pipeline::new(None, first, None);
     let mut files = Vec::new();
     bar.set_position(0);
-    for path in std::fs::read_dir(&args.configs)? {
-        let path = path?.path();
-        if path.extension().is_some_and(|ext| ext == "toml") {
-            let config = fract::config::from_path(path.to_str().unwrap())?;
-
-            if config.width != width || config.height != height {
-                println!("[ERROR] All configs must have the same dimensions");
-                return Ok(ExitCode::FAILURE);
-            }
-
-            pipeline.write_config(|c| {
-                *c = config;
-            });
-            let file_stem = path.file_stem().unwrap().to_str().unwrap();
-            let path = format!("{data_root}/{file_stem}.png");
-            fract::render_png(&mut pipeline, None, &path, 0)?;
-            bar.inc(1);
-            files.push(path);
+    for path in &paths {
+        let config = config_args.load_path(path)?;
+
+        if config.width != width || config.height != height {
+            eprintln!("[ERROR] All configs must have the same dimensions");
+            return Ok(ExitCode::FAILURE);
         }
+
+        pipeline.write_config(|c| {
+            *c = config;
+        });
+        let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let path = format!("{data_root}/{file_stem}.png");
+        fract::render_png(&mut pipeline, None, &path, 0)?;
+        bar.inc(1);
+        files.push(path);
     }
     bar.finish();
 
@@ -113,13 +102,18 @@ fn collage(files: &[String], width: usize, height: usize, output: &str) -> std::
     }
     let rows = count.div_ceil(cols);
     let mut collage = vec![0u8; width * cols * height * rows * 4];
+    // Every tile's metadata is kept in order, so the collage loads as its first tile.
+    let mut metadata = Vec::new();
 
     for (i, file) in files.iter().enumerate() {
         let file = std::fs::File::open(file)?;
         let reader = std::io::BufReader::new(file);
         let mut reader = png::Decoder::new(reader).read_info()?;
         let mut frame = vec![0; reader.output_buffer_size().unwrap()];
-        reader.next_frame(&mut frame).unwrap();
+        reader.next_frame(&mut frame)?;
+        for chunk in reader.info().utf8_text.iter() {
+            metadata.push((chunk.keyword.clone(), chunk.get_text()?));
+        }
 
         let xoffset = (i % cols) * width;
         let yoffset = (i / cols) * height;
@@ -132,5 +126,12 @@ fn collage(files: &[String], width: usize, height: usize, output: &str) -> std::
         }
     }
 
-    fract::encoder::png(output, &collage, width * cols, height * rows, false)
+    fract::encoder::png_with_metadata(
+        output,
+        &collage,
+        width * cols,
+        height * rows,
+        false,
+        &metadata,
+    )
 }
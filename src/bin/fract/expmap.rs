@@ -0,0 +1,183 @@
+use crate::{EncoderArgs, progress_bar};
+use fract::encoder::{ApngEncoder, Encoder, FrameEncoder, GifEncoder};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+use std::{collections::VecDeque, f64::consts::TAU, process::ExitCode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Exponential map png.
+    input: String,
+
+    /// Output file, either `MP4`, `APNG`/`PNG` or `GIF`.
+    output: String,
+
+    /// Number of frames to render.
+    #[arg(short, long, default_value_t = 300)]
+    frames: usize,
+
+    /// Number of frames per second.
+    #[arg(long, default_value_t = 30)]
+    fps: usize,
+
+    /// Width of the video.
+    #[arg(long, default_value_t = 1280)]
+    width: usize,
+
+    /// Height of the video.
+    #[arg(long, default_value_t = 720)]
+    height: usize,
+
+    #[command(flatten)]
+    encoder: EncoderArgs,
+}
+
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    if args.frames < 2 || args.width == 0 || args.height == 0 {
+        eprintln!("[ERROR] Expected at least 2 frames and a non-zero video size");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let format = args.output.to_lowercase();
+    let (w, h) = (args.width, args.height);
+    if format.ends_with(".mp4") {
+        let options = args.encoder.options(false);
+        let encoder = Encoder::new(&args.output, String::new(), w, h, args.fps, &options)?;
+        reassemble(&args, encoder)
+    } else if format.ends_with(".apng") || format.ends_with(".png") {
+        let encoder = ApngEncoder::new(&args.output, w, h, args.fps, args.frames)?;
+        reassemble(&args, encoder)
+    } else if format.ends_with(".gif") {
+        let encoder = GifEncoder::new(&args.output, w, h, args.fps)?;
+        reassemble(&args, encoder)
+    } else {
+        eprintln!("[ERROR] Invalid video format, expected MP4, APNG or GIF");
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Rows of the map around the current frame, decoded from the top (outermost) down.
+struct Window {
+    rows: VecDeque<Vec<u8>>,
+    /// Ring index of the first row in `rows`, where ring 0 is the bottom of the map.
+    top: usize,
+    width: usize,
+}
+
+impl Window {
+    /// Bilinearly sample the map at column `x` and ring `ring`, clamping to the
+    /// decoded rows and wrapping around the angle.
+    fn sample(&self, x: f64, ring: f64) -> [f32; 4] {
+        let bottom = self.top + 1 - self.rows.len();
+        let ring = ring.clamp(bottom as f64, self.top as f64);
+        let r0 = ring.floor() as usize;
+        let r1 = (r0 + 1).min(self.top);
+        let fr = (ring - r0 as f64) as f32;
+
+        let x = x.rem_euclid(self.width as f64);
+        let x0 = x.floor() as usize % self.width;
+        let x1 = (x0 + 1) % self.width;
+        let fx = (x - x.floor()) as f32;
+
+        let texel = |x: usize, ring: usize| {
+            let row = &self.rows[self.top - ring];
+            let p = &row[x * 4..x * 4 + 4];
+            [p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32]
+        };
+        let (a, b, c, d) = (texel(x0, r0), texel(x1, r0), texel(x0, r1), texel(x1, r1));
+        std::array::from_fn(|i| {
+            let near = a[i] + (b[i] - a[i]) * fx;
+            let far = c[i] + (d[i] - c[i]) * fx;
+            near + (far - near) * fr
+        })
+    }
+}
+
+fn reassemble(args: &Args, mut encoder: impl FrameEncoder) -> std::io::Result<ExitCode> {
+    let file = std::io::BufReader::new(std::fs::File::open(&args.input)?);
+    let mut reader = png::Decoder::new(file).read_info()?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        eprintln!("[ERROR] Expected an 8-bit RGBA exponential map");
+        return Ok(ExitCode::FAILURE);
+    }
+    let map_width = info.width as usize;
+    let map_height = info.height as usize;
+
+    // Rings per unit of log radius, and the distance in rings from the top edge of a
+    // frame to its corners and to the pixels next to its centre.
+    let rings = map_width as f64 / TAU;
+    let (w, h) = (args.width as f64, args.height as f64);
+    let corner = rings * (1.0 + (w / h).powi(2)).sqrt().ln();
+    let centre = rings * (h / 2.0).ln();
+
+    // The ring at the top edge of the frame, going from the outermost frame that fits
+    // in the map to the location of the map, which sits half a ring below ring 0.
+    let start = map_height as f64 - 1.0 - corner;
+    let end = -0.5;
+    if start <= end {
+        eprintln!(
+            "[ERROR] Exponential map is too short for a {}x{} video",
+            args.width, args.height
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let bar = progress_bar("frames", args.frames);
+
+    let mut window = Window {
+        rows: VecDeque::new(),
+        top: map_height - 1,
+        width: map_width,
+    };
+    let mut decoded = 0;
+    let mut frame = vec![0u8; args.width * args.height * 4];
+    for i in 0..args.frames {
+        let edge = start + (end - start) * i as f64 / (args.frames - 1) as f64;
+
+        // Decode down to the rings next to the centre and drop the rings beyond the corners.
+        let lowest = (edge - centre - 1.0).floor().max(0.0) as usize;
+        while decoded < map_height && map_height - decoded > lowest {
+            let mut row = vec![0; map_width * 4];
+            reader.read_row(&mut row)?;
+            window.rows.push_back(row);
+            decoded += 1;
+        }
+        let highest = ((edge + corner).ceil() as usize + 1).min(map_height - 1);
+        while window.top > highest && window.rows.len() > 1 {
+            window.rows.pop_front();
+            window.top -= 1;
+        }
+
+        frame
+            .par_chunks_mut(args.width * 4)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let v = (h / 2.0 - y as f64 - 0.5) / (h / 2.0);
+                for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                    let u = (x as f64 + 0.5 - w / 2.0) / (h / 2.0);
+                    let ring = edge + rings * u.hypot(v).ln();
+                    let column = v.atan2(u).rem_euclid(TAU) * rings - 0.5;
+                    let [r, g, b, a] = window.sample(column, ring);
+                    for (dst, src) in pixel.iter_mut().zip([b, g, r, a]) {
+                        *dst = src.round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+            });
+
+        encoder.render_frame(&frame)?;
+        bar.inc(1);
+    }
+    bar.finish();
+
+    encoder.finish()?;
+    println!(
+        "[LOG] Wrote {} bytes to {}",
+        std::fs::metadata(&args.output)?.len(),
+        args.output
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
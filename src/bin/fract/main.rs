@@ -0,0 +1,157 @@
+use clap::{Parser, Subcommand};
+use fract::{config::Config, encoder::EncoderOptions};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::process::ExitCode;
+
+mod assemble;
+mod buddhabrot;
+mod collage;
+mod expmap;
+mod permute;
+mod render;
+mod viewer;
+
+/// Hardware accelerated viewer and renderer of the Mandelbrot set.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Explore the Mandelbrot set interactively.
+    Viewer(viewer::Args),
+    /// Render an image, video or exponential map.
+    Render(render::Args),
+    /// Write every permutation of a set of config values.
+    Permute(permute::Args),
+    /// Render a directory of configs into a collage.
+    Collage(collage::Args),
+    /// Render a buddhabrot.
+    Buddhabrot(buddhabrot::Args),
+    /// Reassemble an exponential map into a zoom video.
+    Expmap(expmap::Args),
+    /// Encode the frames of a distributed render into a video.
+    Assemble(assemble::Args),
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Viewer(args) => viewer::run(args),
+        Command::Render(args) => render::run(args),
+        Command::Permute(args) => permute::run(args),
+        Command::Collage(args) => collage::run(args),
+        Command::Buddhabrot(args) => buddhabrot::run(args),
+        Command::Expmap(args) => expmap::run(args),
+        Command::Assemble(args) => assemble::run(args),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("[ERROR] {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Config file and field overrides shared by every subcommand that renders a config.
+#[derive(clap::Args, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct ConfigArgs {
+    /// Path to a config toml, or to a png rendered by fract.
+    #[arg(short, long)]
+    pub config: Option<String>,
+
+    /// Profile of the config to apply, e.g. `draft` for `[profile.draft]`.
+    #[arg(long, requires = "config")]
+    pub profile: Option<String>,
+
+    /// Override a config field, e.g. `--set iterations=50000 --set palette=inferno`.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    #[serde(default)]
+    pub overrides: Vec<String>,
+}
+
+impl ConfigArgs {
+    /// Load the config with its profile, or the default config if no path was given,
+    /// and apply the overrides.
+    pub fn load(&self) -> std::io::Result<Config> {
+        match &self.config {
+            Some(path) => self.load_path(std::path::Path::new(path)),
+            None => self.apply(Config::default()),
+        }
+    }
+
+    /// Load the config at `path` instead of `config`, with the profile and overrides.
+    pub fn load_path(&self, path: &std::path::Path) -> std::io::Result<Config> {
+        let Some(path) = path.to_str() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a utf-8 path", path.display()),
+            ));
+        };
+        self.apply(fract::config::load(path, self.profile.as_deref())?)
+    }
+
+    fn apply(&self, mut config: Config) -> std::io::Result<Config> {
+        for assignment in self.overrides.iter() {
+            config.set(assignment)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// ffmpeg settings shared by every subcommand that encodes an `MP4`.
+#[derive(clap::Args, Debug, serde::Deserialize, serde::Serialize)]
+pub struct EncoderArgs {
+    /// Video codec passed to ffmpeg.
+    #[arg(long, default_value = "libx264")]
+    pub codec: String,
+
+    /// Constant rate factor passed to ffmpeg.
+    #[arg(long, default_value_t = 23)]
+    pub crf: u32,
+
+    /// Encoder preset passed to ffmpeg.
+    #[arg(long, default_value = "medium")]
+    pub preset: String,
+
+    /// Output pixel format passed to ffmpeg.
+    #[arg(long, default_value = "yuv420p")]
+    pub pix_fmt: String,
+
+    /// Path to the ffmpeg executable.
+    #[arg(long, default_value = "ffmpeg")]
+    pub ffmpeg: String,
+}
+
+impl EncoderArgs {
+    pub fn options(&self, keep_frames: bool) -> EncoderOptions {
+        EncoderOptions {
+            program: self.ffmpeg.clone(),
+            codec: self.codec.clone(),
+            crf: self.crf,
+            preset: self.preset.clone(),
+            pix_fmt: self.pix_fmt.clone(),
+            keep_frames,
+        }
+    }
+}
+
+/// Progress bar that counts `len` items of `unit`.
+pub fn progress_bar(unit: &str, len: usize) -> ProgressBar {
+    let bar = ProgressBar::new(len as u64);
+    let width = len.to_string().len().max(4);
+    bar.set_style(
+        ProgressStyle::with_template(&format!(
+            "[{{elapsed_precise}}] {{prefix}}{{bar:40.cyan/blue}} \
+                {unit}={{pos:>{width}}}/{{len:{width}}} eta={{eta_precise}}",
+        ))
+        .unwrap()
+        .progress_chars("##-"),
+    );
+    bar
+}
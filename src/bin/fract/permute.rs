@@ -1,16 +1,15 @@
-use clap::Parser;
+use crate::ConfigArgs;
+use fract::config::Preset;
 use std::process::ExitCode;
 
-/// Config permutation utility.
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[derive(clap::Args, Debug)]
+pub struct Args {
     /// Output directory.
     output: String,
 
-    /// Path to a config toml to initialize the permutations.
-    #[arg(short, long)]
-    config: Option<String>,
+    /// Config to initialize the permutations.
+    #[command(flatten)]
+    config: ConfigArgs,
 
     /// Path to a permutation config toml.
     #[arg(short, long)]
@@ -24,7 +23,7 @@ struct Args {
 #[derive(serde::Deserialize)]
 struct Permute {
     iterations: Vec<usize>,
-    palette: Vec<String>,
+    palette: Vec<Preset>,
     color_scale: ColorScalePermute,
 }
 
@@ -36,24 +35,21 @@ struct ColorScalePermute {
 }
 
 fn from_path(path: &str) -> std::io::Result<Permute> {
-    match toml::from_str(&std::fs::read_to_string(path)?) {
-        Ok(config) => Ok(config),
-        Err(err) => {
-            println!("[ERROR] Failed to parse config: {err}");
-            Err(std::io::ErrorKind::Other.into())
-        }
-    }
+    let toml = std::fs::read_to_string(path)
+        .map_err(|err| std::io::Error::new(err.kind(), format!("failed to read {path}: {err}")))?;
+    toml::from_str(&toml).map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("failed to parse {path}: {err}"),
+        )
+    })
 }
 
-fn main() -> std::io::Result<ExitCode> {
-    let args = Args::parse();
-    let mut config = args
-        .config
-        .and_then(|path| fract::config::from_path(&path).ok())
-        .unwrap_or_default();
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    let mut config = args.config.load()?;
     let permute = from_path(&args.permute)?;
     if permute.color_scale.steps == 0 {
-        println!("[ERROR] color_scale steps must be 1 or more");
+        eprintln!("[ERROR] color_scale steps must be 1 or more");
         return Ok(ExitCode::FAILURE);
     }
 
@@ -84,7 +80,7 @@ fn main() -> std::io::Result<ExitCode> {
 
             for step in 0..permute.color_scale.steps {
                 config.iterations = *iterations;
-                config.palette = palette.clone();
+                config.palette = *palette;
                 config.color_scale = permute.color_scale.start + inc * step as f32;
 
                 fract::config::write_to(&config, &format!("{configs}{split}/{i}.toml"))?;
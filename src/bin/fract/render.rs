@@ -0,0 +1,574 @@
+use crate::{ConfigArgs, EncoderArgs, progress_bar};
+use fract::{
+    encoder::{ApngEncoder, Encoder, FrameEncoder, FrameManifest, GifEncoder, PngSequence},
+    pipeline::{LoggedFrame, Pipeline},
+    timeline::{Easing, Keyframe, Timeline},
+};
+use indicatif::ProgressBar;
+use malachite_float::Float;
+use std::{ops::Range, process::ExitCode, time::UNIX_EPOCH};
+
+#[derive(clap::Args, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Args {
+    /// Output file, either `PNG` for images or `MP4`, `APNG`/`PNG` or `GIF` for videos.
+    ///
+    /// Frame ranges are written to a directory shared by every range of the video.
+    #[arg(required_unless_present = "resume")]
+    output: Option<String>,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    config: ConfigArgs,
+
+    /// Number of frames to render.
+    #[arg(short, long, default_value_t = 1)]
+    frames: usize,
+
+    /// Number of frames per second.
+    #[arg(long, default_value_t = 30)]
+    fps: usize,
+
+    /// Factor added to the zoom every frame.
+    #[arg(short, long, default_value_t = -0.05)]
+    zoom: f32,
+
+    /// Zoom at the end of an exact zoom video, as an arbitrary-precision string.
+    ///
+    /// Every frame's zoom is interpolated geometrically from the start zoom, so the
+    /// video ends on exactly this zoom.
+    #[arg(long, conflicts_with_all = ["zoom", "rotate", "keyframes", "exp_map"])]
+    zoom_to: Option<String>,
+
+    /// Zoom at the start of an exact zoom video. Defaults to the config zoom.
+    #[arg(long, requires = "zoom_to")]
+    zoom_from: Option<String>,
+
+    /// Length of an exact zoom video in seconds, instead of a number of frames.
+    #[arg(long, requires = "zoom_to", conflicts_with = "frames")]
+    duration: Option<f64>,
+
+    /// Easing of an exact zoom video.
+    #[arg(long, value_enum, default_value_t = Easing::Linear, requires = "zoom_to")]
+    easing: Easing,
+
+    /// Seconds to hold the final frame of an exact zoom video.
+    #[arg(long, default_value_t = 0.0, requires = "zoom_to")]
+    hold: f64,
+
+    /// Degrees added to the rotation every frame.
+    #[arg(long, default_value_t = 0.0, conflicts_with = "keyframes")]
+    rotate: f32,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    encoder: EncoderArgs,
+
+    /// Keep every frame as a png in the data directory.
+    #[arg(long, default_value_t = false)]
+    keep_frames: bool,
+
+    /// Only render keyframes at every power-of-two zoom level and interpolate
+    /// the frames in between.
+    #[arg(long, default_value_t = false)]
+    keyframes: bool,
+
+    /// Size of the keyframes relative to the video frames.
+    #[arg(long, default_value_t = 2.0)]
+    margin: f64,
+
+    /// Render an exponential map of the location to a tall `PNG` instead.
+    ///
+    /// The config width sets the samples per ring. Use `expmap` to turn the
+    /// map into a zoom video.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["frames", "keyframes"])]
+    exp_map: bool,
+
+    /// Path to a timeline toml of keyframes that animate the view, palette and
+    /// iterations.
+    ///
+    /// The number of frames is derived from the timeline's duration and the fps.
+    #[arg(short, long, conflicts_with_all = ["frames", "zoom", "zoom_to", "rotate", "keyframes", "exp_map"])]
+    timeline: Option<String>,
+
+    /// First frame of a range of the video to render into the shared output directory.
+    ///
+    /// Frames are numbered globally, so ranges can be rendered on separate machines
+    /// and combined with `assemble`.
+    #[arg(long, conflicts_with_all = ["keyframes", "exp_map"])]
+    start_frame: Option<usize>,
+
+    /// Frame after the last frame of the range to render. Defaults to the end of the video.
+    #[arg(long, conflicts_with_all = ["keyframes", "exp_map"])]
+    end_frame: Option<usize>,
+
+    /// Resume an interrupted `MP4` render from its data directory.
+    ///
+    /// The render must have been started with `--keep-frames`. All other
+    /// arguments are restored from the data directory.
+    #[arg(long, conflicts_with = "output")]
+    #[serde(skip)]
+    resume: Option<String>,
+}
+
+enum VideoFormat {
+    Mp4,
+    Apng,
+    Gif,
+    /// A range of png frames in a shared directory.
+    Frames(Range<usize>),
+}
+
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    if args.frames == 0 {
+        eprintln!("[ERROR] Frames must be greater than 0");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let (args, config, data_root, completed) = if let Some(data_root) = args.resume {
+        let args = toml::from_str::<Args>(&std::fs::read_to_string(format!(
+            "{data_root}/render.toml"
+        ))?)
+        .map_err(std::io::Error::other)?;
+        let config = fract::config::from_path(&format!("{data_root}/config.toml"))?;
+        let log_text = std::fs::read_to_string(format!("{data_root}/log.txt"))?;
+        let mut log = fract::pipeline::read_log(&log_text)?;
+        let completed = completed_frames(&data_root, &log);
+
+        if completed == 0 {
+            eprintln!(
+                "[ERROR] No completed frames in {data_root}/frames, \
+                resuming requires a render started with --keep-frames"
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+
+        // Drop any entries past the completed frames, and an interrupted entry, so
+        // the log can be appended to.
+        let end = match log_text.match_indices("[FRAME] ").nth(completed) {
+            Some((end, _)) => end,
+            None => log_text.rfind("\n\n").map_or(0, |end| end + 2),
+        };
+        std::fs::write(format!("{data_root}/log.txt"), &log_text[..end])?;
+
+        println!("[RENDER] Resuming {data_root} at frame {completed}");
+        (
+            args,
+            config,
+            data_root,
+            Some(log.swap_remove(completed - 1)),
+        )
+    } else {
+        let config = args.config.load()?;
+
+        let current_time = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let data_root = format!("data/{current_time}");
+        _ = std::fs::create_dir_all(&data_root);
+
+        fract::config::write_to(&config, &format!("{data_root}/config.toml"))?;
+        std::fs::write(
+            format!("{data_root}/render.toml"),
+            toml::to_string(&args).map_err(std::io::Error::other)?,
+        )?;
+        std::fs::File::create(format!("{data_root}/log.txt"))?;
+        if let Some(timeline) = &args.timeline {
+            std::fs::copy(timeline, format!("{data_root}/timeline.toml"))?;
+        }
+
+        (args, config, data_root, None)
+    };
+    let output = args.output.clone().unwrap();
+
+    if args.zoom_to.is_some() && zoom_frames(&args) < 2 {
+        eprintln!("[ERROR] Exact zoom videos require a duration or at least 2 frames");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let timeline = if args.timeline.is_some() {
+        Some(Timeline::from_path(&format!("{data_root}/timeline.toml"))?)
+    } else {
+        args.zoom_to.as_ref().map(|end| zoom_target(&args, end))
+    };
+    let timeline = match timeline
+        .map(|timeline| timeline.resolve(&config))
+        .transpose()
+    {
+        Ok(timeline) => timeline,
+        Err(err) => {
+            eprintln!("[ERROR] Invalid timeline: {err}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let frames = timeline
+        .as_ref()
+        .map_or(args.frames, |timeline| timeline.frames(args.fps));
+
+    let range = (args.start_frame.is_some() || args.end_frame.is_some())
+        .then(|| args.start_frame.unwrap_or(0)..args.end_frame.unwrap_or(frames));
+    if let Some(range) = &range
+        && (frames < 2 || range.is_empty() || range.end > frames)
+    {
+        eprintln!(
+            "[ERROR] Invalid frame range {}..{} of a {frames} frame video",
+            range.start, range.end
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let log_path = format!("{data_root}/log.txt");
+    let log_file = std::fs::File::options().append(true).open(&log_path)?;
+    let log = std::io::BufWriter::new(log_file);
+
+    let width = config.width;
+    let height = config.height;
+    let mut pipeline_config = config.clone();
+    if args.exp_map {
+        // Rows per tile, which keeps every ring within a factor of e^pi of the
+        // tile's zoom so the deltas stay well within f32 range.
+        pipeline_config.height = (width / 2).max(1);
+    } else if args.keyframes {
+        pipeline_config.width = (width as f64 * args.margin).ceil() as usize;
+        pipeline_config.height = (height as f64 * args.margin).ceil() as usize;
+    }
+    let mut pipeline = Pipeline::new(None, pipeline_config, Some(Box::new(log)));
+    if let Some(timeline) = &timeline {
+        timeline.apply(&mut pipeline, 0.0);
+    }
+
+    let kind = if args.exp_map {
+        "exponential map"
+    } else if frames == 1 {
+        "image"
+    } else {
+        "video"
+    };
+    let fps = if frames != 1 {
+        format!("{frames} frames @ {}fps", args.fps)
+    } else {
+        String::default()
+    };
+    println!("[RENDER] {}x{} {kind} {fps}", config.width, config.height,);
+    config.log();
+
+    if args.exp_map {
+        if !output.to_lowercase().ends_with(".png") {
+            eprintln!("[ERROR] Invalid exponential map format, expected PNG");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let bar = progress_bar("tiles", 0);
+
+        fract::render_exp_map(&mut pipeline, Some(&bar), &output)?;
+    } else if frames == 1 {
+        if !output.to_lowercase().ends_with(".png") {
+            eprintln!("[ERROR] Invalid image format, expected PNG");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let bar = progress_bar("pixels", pipeline.total_pixels());
+
+        fract::render_png(&mut pipeline, Some(&bar), &output, 0)?;
+    } else {
+        let format = output.to_lowercase();
+        let format = if let Some(range) = range.clone() {
+            VideoFormat::Frames(range)
+        } else if format.ends_with(".mp4") {
+            VideoFormat::Mp4
+        } else if format.ends_with(".apng") || format.ends_with(".png") {
+            VideoFormat::Apng
+        } else if format.ends_with(".gif") {
+            VideoFormat::Gif
+        } else {
+            eprintln!("[ERROR] Invalid video format, expected MP4, APNG or GIF");
+            return Ok(ExitCode::FAILURE);
+        };
+
+        if completed.is_some() && (args.keyframes || !matches!(format, VideoFormat::Mp4)) {
+            eprintln!("[ERROR] Only MP4 renders without keyframes can be resumed");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        if args.keyframes && (args.margin < 2.0 || args.zoom <= -1.0) {
+            eprintln!("[ERROR] Keyframes require a margin of at least 2 and a zoom above -1");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let bar = progress_bar("frames", frames);
+
+        // Every frame's position is computed directly from its index, so any range of
+        // frames can be rendered without replaying the frames before it.
+        let start_zoom = pipeline.read_position(|_, _, z| z.clone());
+        let zoom_factor = Float::from(1.0 + args.zoom as f64);
+        let prec = fract::precision::headroom([&start_zoom]);
+        let update = |pipeline: &mut Pipeline, frame: usize| match &timeline {
+            Some(timeline) => timeline.apply(pipeline, frame as f64 / args.fps as f64),
+            None => {
+                pipeline.write_position(|_, _, z| {
+                    *z = &start_zoom * fract::pow_prec(&zoom_factor, frame as u64 + 1, prec);
+                });
+                if args.rotate != 0.0 {
+                    pipeline.set_rotation(config.rotation + args.rotate * (frame + 1) as f32);
+                }
+            }
+        };
+
+        // Move to the last completed frame to check the resumed position against the log.
+        let start = completed.as_ref().map_or(0, |logged| logged.frame + 1);
+        if let Some(logged) = &completed {
+            update(&mut pipeline, logged.frame);
+            // The log holds decimal strings, so compare values rounded to the precision
+            // of the recomputed position rather than their formatting.
+            let same = |value: &Float, logged: &str| {
+                fract::float_from_str(logged).is_some_and(|mut logged| {
+                    if let Some(prec) = value.get_prec() {
+                        logged.set_prec(prec);
+                    }
+                    logged == *value
+                })
+            };
+            let matches = pipeline.read_position(|x, y, z| {
+                same(x, &logged.x) && same(y, &logged.y) && same(z, &logged.zoom)
+            }) && pipeline.read_config(|config| config.rotation) == logged.rotation;
+            if !matches {
+                eprintln!(
+                    "[ERROR] Resumed position does not match the log at frame {}",
+                    logged.frame
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+
+        match format {
+            VideoFormat::Mp4 => {
+                let options = args.encoder.options(args.keep_frames);
+                let mut encoder =
+                    Encoder::new(&output, data_root, width, height, args.fps, &options)?;
+                encoder.replay_frames(start)?;
+                let frame_path = encoder.frame_path().map(str::to_string);
+                render_video(
+                    &mut pipeline,
+                    &bar,
+                    encoder,
+                    &args,
+                    (width, height),
+                    start..frames,
+                    update,
+                )?;
+
+                if let Some(frame_path) = frame_path {
+                    println!("[LOG] Wrote {frames} images to {frame_path}");
+                }
+            }
+            VideoFormat::Apng => {
+                let encoder = ApngEncoder::new(&output, width, height, args.fps, frames)?;
+                render_video(
+                    &mut pipeline,
+                    &bar,
+                    encoder,
+                    &args,
+                    (width, height),
+                    0..frames,
+                    update,
+                )?;
+            }
+            VideoFormat::Gif => {
+                let encoder = GifEncoder::new(&output, width, height, args.fps)?;
+                render_video(
+                    &mut pipeline,
+                    &bar,
+                    encoder,
+                    &args,
+                    (width, height),
+                    0..frames,
+                    update,
+                )?;
+            }
+            VideoFormat::Frames(range) => {
+                // Move to the last frame and back, so renders of the same config that
+                // move differently can't share a directory.
+                let start = pipeline.position();
+                update(&mut pipeline, frames - 1);
+                let end = pipeline.position();
+                match &timeline {
+                    Some(timeline) => timeline.apply(&mut pipeline, 0.0),
+                    None => {
+                        pipeline.write_position(|_, _, z| *z = start_zoom.clone());
+                        pipeline.set_rotation(config.rotation);
+                    }
+                }
+
+                let manifest = FrameManifest {
+                    frames,
+                    fps: args.fps,
+                    width,
+                    height,
+                    config: toml::to_string(&config).expect("config serializes to toml"),
+                    start,
+                    end,
+                };
+                manifest.write_to(&output)?;
+                let encoder = PngSequence::new(&output, width, height, range.start)?;
+                let images = range.len();
+                render_video(
+                    &mut pipeline,
+                    &bar,
+                    encoder,
+                    &args,
+                    (width, height),
+                    range,
+                    update,
+                )?;
+
+                println!("[LOG] Wrote {images} images to {output}/frames");
+                let missing = manifest.missing_frames(&output).len();
+                if missing > 0 {
+                    println!("[LOG] {missing} frames remaining before `assemble`");
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+        }
+    };
+
+    println!(
+        "[LOG] Wrote {} bytes to {}",
+        std::fs::metadata(&log_path)?.len(),
+        log_path
+    );
+    println!(
+        "[LOG] Wrote {} bytes to {}",
+        std::fs::metadata(&output)?.len(),
+        output
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Render `frames` into `encoder`, either directly or from keyframes.
+fn render_video(
+    pipeline: &mut Pipeline,
+    bar: &ProgressBar,
+    encoder: impl FrameEncoder,
+    args: &Args,
+    (width, height): (usize, usize),
+    frames: Range<usize>,
+    update: impl FnMut(&mut Pipeline, usize),
+) -> std::io::Result<()> {
+    if args.keyframes {
+        let octaves_per_frame = -(1.0 + args.zoom as f64).log2();
+        fract::render_keyframes(
+            pipeline,
+            Some(bar),
+            encoder,
+            frames,
+            width,
+            height,
+            args.margin,
+            |i| (i + 1) as f64 * octaves_per_frame,
+        )
+    } else {
+        fract::render_mp4(pipeline, Some(bar), encoder, frames, update)
+    }
+}
+
+/// Timeline that zooms from `--zoom-from` to `end` over `--duration` or `--frames`,
+/// then holds the final frame for `--hold` seconds.
+fn zoom_target(args: &Args, end: &str) -> Timeline {
+    // Frames are sampled at `i / fps`, so snapping the duration to a frame lands the
+    // last frame exactly on the end zoom.
+    let duration = (zoom_frames(args) - 1) as f64 / args.fps as f64;
+    let mut keyframes = vec![
+        Keyframe {
+            time: 0.0,
+            zoom: args.zoom_from.clone(),
+            easing: args.easing,
+            ..Default::default()
+        },
+        Keyframe {
+            time: duration,
+            zoom: Some(end.to_string()),
+            ..Default::default()
+        },
+    ];
+    if args.hold > 0.0 {
+        keyframes.push(Keyframe {
+            time: duration + args.hold,
+            ..Default::default()
+        });
+    }
+    Timeline { keyframes }
+}
+
+/// Number of frames spent zooming in an exact zoom video.
+fn zoom_frames(args: &Args) -> usize {
+    args.duration.map_or(args.frames, |duration| {
+        (duration * args.fps as f64).round() as usize + 1
+    })
+}
+
+/// Number of leading frames in `data_root` that were both saved and logged.
+fn completed_frames(data_root: &str, log: &[LoggedFrame]) -> usize {
+    log.iter()
+        .enumerate()
+        .take_while(|(i, logged)| {
+            logged.frame == *i
+                && std::path::Path::new(&format!("{data_root}/frames/{i}.png")).exists()
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logged(frame: usize) -> LoggedFrame {
+        LoggedFrame {
+            frame,
+            x: "-0.75".to_string(),
+            y: "0.1".to_string(),
+            zoom: "1".to_string(),
+            rotation: 0.0,
+            iterations: 1000,
+            precision: 128,
+        }
+    }
+
+    #[test]
+    fn completed_frames_are_saved_and_logged() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("frames")).unwrap();
+        let data_root = dir.to_string_lossy().into_owned();
+        let save = |frames: &[usize]| {
+            for frame in frames {
+                std::fs::write(dir.join(format!("frames/{frame}.png")), b"").unwrap();
+            }
+        };
+
+        let log = (0..4).map(logged).collect::<Vec<_>>();
+        assert_eq!(completed_frames(&data_root, &log), 0);
+        assert_eq!(completed_frames(&data_root, &[]), 0);
+
+        // Saved frames that were not logged yet, and logged frames that were not saved.
+        save(&[0, 1, 2]);
+        assert_eq!(completed_frames(&data_root, &log[..2]), 2);
+        assert_eq!(completed_frames(&data_root, &log), 3);
+
+        // A frame missing in the middle ends the completed frames.
+        save(&[4, 5]);
+        let log = (0..6).map(logged).collect::<Vec<_>>();
+        assert_eq!(completed_frames(&data_root, &log), 3);
+
+        // As does a gap in the log.
+        save(&[3]);
+        assert_eq!(completed_frames(&data_root, &log), 6);
+        let gap = [logged(0), logged(1), logged(3)];
+        assert_eq!(completed_frames(&data_root, &gap), 2);
+
+        // Partially written frames are never complete.
+        std::fs::remove_file(dir.join("frames/1.png")).unwrap();
+        std::fs::write(dir.join("frames/1.png.part"), b"").unwrap();
+        assert_eq!(completed_frames(&data_root, &log), 1);
+    }
+}
@@ -0,0 +1,36 @@
+use crate::ConfigArgs;
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// Fraction of the view height panned by the arrow and WASD keys.
+    #[arg(long, default_value_t = 0.1)]
+    pan_speed: f64,
+
+    /// Show the overlay with the location and render progress, toggled with `H`.
+    #[arg(long, default_value_t = false)]
+    hud: bool,
+}
+
+pub fn run(args: Args) -> std::io::Result<ExitCode> {
+    let config = args.config.load()?;
+    config.log();
+
+    // Bookmarks are kept next to the config, e.g. `config.bookmarks.toml`.
+    let bookmarks = match &args.config.config {
+        Some(path) => Path::new(path).with_extension("bookmarks.toml"),
+        None => PathBuf::from("bookmarks.toml"),
+    };
+
+    let memory = fract::viewer::Memory::from_config(config)
+        .with_pan_speed(args.pan_speed)
+        .with_hud(args.hud)
+        .with_bookmarks(bookmarks);
+    fract::viewer::run(memory);
+}
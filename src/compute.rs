@@ -13,7 +13,46 @@ struct MandelbrotUniform {
     batch_iter: i32,
     palette_len: f32,
     color_scale: f32,
+    palette_offset: f32,
     color_mode: i32,
+    mapping: i32,
+    rotation_cos: f32,
+    rotation_sin: f32,
+}
+
+/// State of a pixel, matching `OrbitState` in `mandelbrot.wgsl`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PixelState {
+    /// Delta from the reference orbit, scaled by `2^q`.
+    pub dx: f32,
+    pub dy: f32,
+    /// Iterations so far.
+    pub j: i32,
+    /// Index into the reference orbit.
+    pub k: i32,
+    /// Scale exponent of the delta.
+    pub q: i32,
+    pub finished: u32,
+}
+
+impl PixelState {
+    /// The delta was rebased onto the start of the reference orbit, which sets `k` back
+    /// while `j` keeps counting.
+    pub fn rebased(&self) -> bool {
+        self.k != self.j
+    }
+}
+
+/// How pixels are mapped to offsets from the reference orbit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mapping {
+    /// Regular axis-aligned view.
+    #[default]
+    Rectilinear,
+    /// Columns are angles around the centre and rows are rings whose radius grows
+    /// exponentially from the bottom row, which has a radius of the zoom.
+    Exponential,
 }
 
 /// Perform iterative mandelbrot computation in a compute shader.
@@ -22,12 +61,15 @@ struct MandelbrotUniform {
 /// in order to prevent the device from timing out.
 pub struct ComputePipeline {
     pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
     uniform: wgpu::Buffer,
     pixel_state: wgpu::Buffer,
     pixel_state_bytes: u64,
     remaining: wgpu::Buffer,
     remaining_stage: wgpu::Buffer,
+    /// Staging buffer for the state of a single pixel.
+    pixel_stage: wgpu::Buffer,
 }
 
 impl ComputePipeline {
@@ -44,31 +86,6 @@ impl ComputePipeline {
             mapped_at_creation: false,
         });
 
-        let sf = ssaa.ssaa_factor();
-        let pixel_state_bytes =
-            (std::mem::size_of::<f32>() * 6 * config.width * sf * config.height * sf) as u64;
-        let pixel_state = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: pixel_state_bytes,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let remaining = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: pixel_state_bytes,
-            usage: wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_DST
-                | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let remaining_stage = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: pixel_state_bytes,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
-
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
             entries: &[
@@ -115,28 +132,16 @@ impl ComputePipeline {
             ],
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(ssaa.render_target()),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: uniform.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: pixel_state.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: remaining.as_entire_binding(),
-                },
-            ],
-        });
+        let (pixel_state, pixel_state_bytes, remaining, remaining_stage) =
+            Self::pixel_buffers(device, ssaa, config.width, config.height);
+        let bind_group = Self::bind_group(
+            device,
+            &bind_group_layout,
+            ssaa,
+            &uniform,
+            &pixel_state,
+            &remaining,
+        );
 
         let module = device.create_shader_module(wgpu::include_wgsl!("shaders/mandelbrot.wgsl"));
 
@@ -171,31 +176,126 @@ impl ComputePipeline {
 
         Self {
             pipeline,
+            bind_group_layout,
             bind_group,
             uniform,
             pixel_state,
             pixel_state_bytes,
             remaining,
             remaining_stage,
+            pixel_stage: device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: std::mem::size_of::<PixelState>() as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
         }
     }
 
+    /// Reallocate the per-pixel buffers for a `width` by `height` output rendered
+    /// into the textures of `ssaa`, which must already be resized.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        ssaa: &SsaaPipeline,
+        width: usize,
+        height: usize,
+    ) {
+        let (pixel_state, pixel_state_bytes, remaining, remaining_stage) =
+            Self::pixel_buffers(device, ssaa, width, height);
+        self.bind_group = Self::bind_group(
+            device,
+            &self.bind_group_layout,
+            ssaa,
+            &self.uniform,
+            &pixel_state,
+            &remaining,
+        );
+        self.pixel_state = pixel_state;
+        self.pixel_state_bytes = pixel_state_bytes;
+        self.remaining = remaining;
+        self.remaining_stage = remaining_stage;
+    }
+
+    /// Create the state of every pixel, and the count of remaining pixels.
+    fn pixel_buffers(
+        device: &wgpu::Device,
+        ssaa: &SsaaPipeline,
+        width: usize,
+        height: usize,
+    ) -> (wgpu::Buffer, u64, wgpu::Buffer, wgpu::Buffer) {
+        let sf = ssaa.ssaa_factor();
+        let pixel_state_bytes =
+            (std::mem::size_of::<PixelState>() * width * sf * height * sf) as u64;
+        let pixel_state = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: pixel_state_bytes,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let remaining = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: pixel_state_bytes,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let remaining_stage = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: pixel_state_bytes,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        (pixel_state, pixel_state_bytes, remaining, remaining_stage)
+    }
+
+    fn bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        ssaa: &SsaaPipeline,
+        uniform: &wgpu::Buffer,
+        pixel_state: &wgpu::Buffer,
+        remaining: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(ssaa.render_target()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: pixel_state.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: remaining.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
     pub fn write_buffers(
         &self,
         queue: &wgpu::Queue,
         config: &Config,
         z: &Float,
         palette: &Palette,
+        mapping: Mapping,
     ) {
-        let color_mode = match &*config.color_mode {
-            "iterations" => 0,
-            "wave" => 1,
-            "smooth_iterations" => 2,
-            "smooth_wave" => 3,
-            _ => 0,
-        };
-
         let (zm, ze) = to_f32_exp(z);
+        let rotation = (config.rotation as f64).to_radians();
         queue.write_buffer(
             &self.uniform,
             0,
@@ -206,7 +306,11 @@ impl ComputePipeline {
                 batch_iter: config.batch_iter as i32,
                 palette_len: palette.len as f32,
                 color_scale: config.color_scale,
-                color_mode,
+                palette_offset: config.palette_offset,
+                color_mode: config.color_mode as i32,
+                mapping: mapping as i32,
+                rotation_cos: rotation.cos() as f32,
+                rotation_sin: rotation.sin() as f32,
             }]),
         );
         queue
@@ -264,4 +368,48 @@ impl ComputePipeline {
         self.remaining_stage.unmap();
         remaining
     }
+
+    /// Read back the state of the pixel at `index`, counting supersampled pixels row by row.
+    pub fn pixel_state(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        index: usize,
+    ) -> PixelState {
+        let size = std::mem::size_of::<PixelState>() as u64;
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(
+            &self.pixel_state,
+            index as u64 * size,
+            &self.pixel_stage,
+            0,
+            size,
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = self.pixel_stage.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+        let data = slice.get_mapped_range();
+        let word = |i: usize| {
+            [
+                data[4 * i],
+                data[4 * i + 1],
+                data[4 * i + 2],
+                data[4 * i + 3],
+            ]
+        };
+        let state = PixelState {
+            dx: f32::from_le_bytes(word(0)),
+            dy: f32::from_le_bytes(word(1)),
+            j: i32::from_le_bytes(word(2)),
+            k: i32::from_le_bytes(word(3)),
+            q: i32::from_le_bytes(word(4)),
+            finished: u32::from_le_bytes(word(5)),
+        };
+        drop(data);
+        self.pixel_stage.unmap();
+        state
+    }
 }
@@ -1,5 +1,17 @@
+use crate::{
+    error::{Error, Location},
+    float_from_str,
+    orbit::MAX_ITERATIONS,
+    precision::MIN_PRECISION,
+};
+use malachite_float::Float;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub use crate::palette::Preset;
+
 #[derive(Clone, serde::Deserialize, serde::Serialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub x: String,
     pub y: String,
@@ -7,25 +19,324 @@ pub struct Config {
     pub iterations: usize,
     pub width: usize,
     pub height: usize,
-    pub palette: String,
+    pub palette: Preset,
     pub ssaa: bool,
     pub batch_iter: usize,
     pub color_scale: f32,
-    pub color_mode: String,
+    pub palette_offset: f32,
+    pub rotation: f32,
+    pub color_mode: ColorMode,
+    /// Bits of precision of the position, or 0 to derive it from the view.
+    pub precision: u64,
+    /// Corners `[min_x, min_y, max_x, max_y]` of the box set as `bounds`, which is
+    /// fitted again when the size or rotation changes, until the view is set otherwise.
+    #[serde(skip)]
+    bounds: Option<[Float; 4]>,
+}
+
+/// Png text chunk holding the serialized [`Config`] of a render.
+pub const PNG_CONFIG_KEYWORD: &str = "fract:config";
+/// Png text chunk holding the exact [`Position`] of a render.
+pub const PNG_POSITION_KEYWORD: &str = "fract:position";
+
+/// Corners of a box to fit in view, set as `bounds` in place of `x`, `y` and `zoom`.
+///
+/// ```toml
+/// bounds = { min_x = "-2.0", min_y = "-1.25", max_x = "0.5", max_y = "1.25" }
+/// ```
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bounds {
+    pub min_x: String,
+    pub min_y: String,
+    pub max_x: String,
+    pub max_y: String,
+}
+
+/// Exact view of a rendered image, which may have moved away from its config.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Position {
+    pub x: String,
+    pub y: String,
+    pub zoom: String,
+    pub rotation: f32,
+}
+
+/// How escaped pixels are colored, matching the modes in `mandelbrot.wgsl`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Samples the palette by the iteration count.
+    #[default]
+    Iterations = 0,
+    /// Cycles sine waves by the iteration count.
+    Wave = 1,
+    SmoothIterations = 2,
+    SmoothWave = 3,
+}
+
+impl ColorMode {
+    /// Every mode, in the order of their values.
+    pub const ALL: &[ColorMode] = &[
+        Self::Iterations,
+        Self::Wave,
+        Self::SmoothIterations,
+        Self::SmoothWave,
+    ];
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Iterations => "iterations",
+            Self::Wave => "wave",
+            Self::SmoothIterations => "smooth_iterations",
+            Self::SmoothWave => "smooth_wave",
+        })
+    }
 }
 
 impl Config {
+    /// Override a single field from a `key=value` assignment.
+    ///
+    /// `value` is parsed as a toml value, except for string fields, `magnification` and
+    /// `depth` which take it verbatim.
+    pub fn set(&mut self, assignment: &str) -> Result<(), Error> {
+        let Some((key, value)) = assignment.split_once('=') else {
+            return Err(Error::invalid(
+                assignment,
+                "expected an assignment of the form `key=value`",
+            ));
+        };
+        let (key, value) = (key.trim(), value.trim());
+
+        let value = match (
+            key,
+            toml::Table::try_from(&*self)
+                .expect("config serializes to a table")
+                .get(key),
+        ) {
+            ("magnification" | "depth", _) | (_, Some(toml::Value::String(_))) => {
+                toml::Value::String(value.trim_matches('"').to_string())
+            }
+            (key, None) if key != "bounds" => {
+                return Err(Error::invalid(key, "unknown config field"));
+            }
+            _ => {
+                let mut parsed = toml::from_str::<toml::Table>(&format!("{key} = {value}"))
+                    .map_err(|err| Error::invalid(key, err.message()))?;
+                parsed.remove(key).unwrap()
+            }
+        };
+        self.set_value(key, value)
+    }
+
+    /// Replace the field `key` with a toml `value`.
+    ///
+    /// Besides the fields of the config, the view may be set as a `magnification` of
+    /// the default view, as a `depth` in octaves, or as the `bounds` to fit in view,
+    /// see [`Bounds`]. These are converted into `x`, `y` and `zoom`, and bounds are
+    /// fitted again whenever `width`, `height` or `rotation` is set after them.
+    pub fn set_value(&mut self, key: &str, value: toml::Value) -> Result<(), Error> {
+        match key {
+            "magnification" | "depth" | "bounds" => return self.set_view(key, value),
+            _ => {}
+        }
+
+        let mut fields = toml::Table::try_from(&*self).expect("config serializes to a table");
+        if !fields.contains_key(key) {
+            return Err(Error::invalid(key, "unknown config field"));
+        }
+        fields.insert(key.to_string(), value);
+        let mut config: Config = fields
+            .try_into()
+            .map_err(|err: toml::de::Error| Error::invalid(key, err.message()))?;
+        if !matches!(key, "x" | "y" | "zoom") {
+            config.bounds = self.bounds.take();
+        }
+        *self = config;
+        if matches!(key, "width" | "height" | "rotation") {
+            self.fit_bounds();
+        }
+        Ok(())
+    }
+
+    fn set_view(&mut self, key: &str, value: toml::Value) -> Result<(), Error> {
+        match key {
+            "magnification" => {
+                let magnification = match &value {
+                    toml::Value::String(value) => float_from_str(value),
+                    toml::Value::Integer(value) => Some(Float::from(*value)),
+                    toml::Value::Float(value) if value.is_finite() => Some(Float::from(*value)),
+                    _ => None,
+                }
+                .filter(|m| *m > 0u32)
+                .ok_or_else(|| Error::invalid(key, "expected a positive number"))?;
+                // Integer magnifications carry only as many bits as they have digits.
+                let mut two = Float::from(2u32);
+                two.set_prec(magnification.get_prec().unwrap_or(53).max(53));
+                self.zoom = (two / magnification).to_string();
+                self.bounds = None;
+            }
+            "depth" => {
+                let depth = match &value {
+                    toml::Value::String(value) => value.parse::<f64>().ok(),
+                    toml::Value::Integer(value) => Some(*value as f64),
+                    toml::Value::Float(value) => Some(*value),
+                    _ => None,
+                }
+                .filter(|depth| depth.is_finite())
+                .ok_or_else(|| Error::invalid(key, "expected a number of octaves"))?;
+                let whole = depth.floor();
+                let fraction = depth - whole;
+                let whole = whole as i64;
+                // The default view has a zoom of 2, which is the first octave.
+                self.zoom = if fraction == 0.0 {
+                    format!("2^{}", 1 - whole)
+                } else {
+                    (Float::from((-fraction).exp2()) << (1 - whole)).to_string()
+                };
+                self.bounds = None;
+            }
+            _ => {
+                let bounds = Bounds::deserialize(value)
+                    .map_err(|err: toml::de::Error| Error::invalid(key, err.message()))?;
+                let corner = |value: &String| {
+                    float_from_str(value)
+                        .ok_or_else(|| Error::invalid(key, format!("`{value}` is not a number")))
+                };
+                let (x0, y0) = (corner(&bounds.min_x)?, corner(&bounds.min_y)?);
+                let (x1, y1) = (corner(&bounds.max_x)?, corner(&bounds.max_y)?);
+                if x1 <= x0 || y1 <= y0 {
+                    return Err(Error::invalid(
+                        key,
+                        "the max corner must be above and to the right of the min corner",
+                    ));
+                }
+
+                // Sums are exact with enough bits to cover both exponents.
+                let corners = [&x0, &y0, &x1, &y1];
+                let exponents = corners.iter().filter_map(|f| f.get_exponent());
+                let spread = (exponents.clone().max().unwrap_or(0) as i64
+                    - exponents.min().unwrap_or(0) as i64) as u64;
+                let prec = corners
+                    .iter()
+                    .filter_map(|f| f.get_prec())
+                    .max()
+                    .unwrap_or(53)
+                    + spread
+                    + 2;
+                let with_prec = |mut f: Float| {
+                    f.set_prec(prec);
+                    f
+                };
+                self.bounds = Some([x0, y0, x1, y1].map(with_prec));
+                self.fit_bounds();
+            }
+        }
+        Ok(())
+    }
+
+    /// Centre the view on the box set as `bounds`, with the smallest zoom that fits it
+    /// at the current size and rotation.
+    fn fit_bounds(&mut self) {
+        let Some([x0, y0, x1, y1]) = &self.bounds else {
+            return;
+        };
+
+        let half_width = (x1 - x0) >> 1u32;
+        let half_height = (y1 - y0) >> 1u32;
+        // The extents of the rotated box along the axes of the view, which are exact
+        // for rotations by multiples of 90 degrees.
+        let rotation = self.rotation.rem_euclid(180.0);
+        let (sin, cos) = if rotation == 0.0 {
+            (0.0, 1.0)
+        } else if rotation == 90.0 {
+            (1.0, 0.0)
+        } else {
+            let (sin, cos) = (rotation as f64).to_radians().sin_cos();
+            (sin.abs(), cos.abs())
+        };
+        let across = &half_width * Float::from(cos) + &half_height * Float::from(sin);
+        let up = half_width * Float::from(sin) + half_height * Float::from(cos);
+
+        // The zoom is the half height of the view, widened to fit the box.
+        let fit_width = across * Float::from(self.height as u64) / Float::from(self.width as u64);
+        self.x = ((x0 + x1) >> 1u32).to_string();
+        self.y = ((y0 + y1) >> 1u32).to_string();
+        self.zoom = if up >= fit_width {
+            up.to_string()
+        } else {
+            fit_width.to_string()
+        };
+    }
+
+    /// Check that every field holds a value that can be rendered.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.width == 0 {
+            return Err(Error::invalid("width", "must be greater than 0"));
+        }
+        if self.height == 0 {
+            return Err(Error::invalid("height", "must be greater than 0"));
+        }
+        if self.iterations == 0 || self.iterations > MAX_ITERATIONS {
+            return Err(Error::invalid(
+                "iterations",
+                format!("must be between 1 and {MAX_ITERATIONS}, the capacity of the orbit"),
+            ));
+        }
+        if self.batch_iter == 0 {
+            return Err(Error::invalid("batch_iter", "must be greater than 0"));
+        }
+
+        for (field, value) in [("x", &self.x), ("y", &self.y), ("zoom", &self.zoom)] {
+            if float_from_str(value).is_none() {
+                return Err(Error::invalid(field, format!("`{value}` is not a number")));
+            }
+        }
+        if float_from_str(&self.zoom).is_some_and(|zoom| zoom == 0u32) {
+            return Err(Error::invalid("zoom", "must not be 0"));
+        }
+
+        if self.precision != 0 && self.precision < MIN_PRECISION {
+            return Err(Error::invalid(
+                "precision",
+                format!("must be 0 for automatic precision or at least {MIN_PRECISION}"),
+            ));
+        }
+
+        if !self.color_scale.is_finite() || self.color_scale == 0.0 {
+            return Err(Error::invalid(
+                "color_scale",
+                "must be a finite, non-zero number",
+            ));
+        }
+        if !self.palette_offset.is_finite() {
+            return Err(Error::invalid("palette_offset", "must be a finite number"));
+        }
+        if !self.rotation.is_finite() {
+            return Err(Error::invalid("rotation", "must be a finite number"));
+        }
+        Ok(())
+    }
+
     pub fn log(&self) {
         let ssaa = if self.ssaa { "enabled" } else { "disabled" };
+        let precision = match self.precision {
+            0 => "auto".to_string(),
+            bits => bits.to_string(),
+        };
         println!(
             "[CONFIG] {} iterations, method={}, palette={}, ssaa={}, \
-            batch_iter={}, color_scale={}",
+            batch_iter={}, color_scale={}, palette_offset={}, precision={}",
             self.iterations,
             self.color_mode,
             self.palette,
             ssaa,
             self.batch_iter,
             self.color_scale,
+            self.palette_offset,
+            precision,
         );
     }
 }
@@ -39,31 +350,507 @@ impl Default for Config {
             iterations: 10_000,
             width: 600,
             height: 600,
-            palette: "magma".to_string(),
+            palette: Preset::Magma,
             ssaa: false,
             batch_iter: 1000,
             color_scale: 2.0,
-            color_mode: "iterations".to_string(),
+            palette_offset: 0.0,
+            rotation: 0.0,
+            color_mode: ColorMode::Iterations,
+            precision: 0,
+            bounds: None,
         }
     }
 }
 
-pub fn from_path(path: &str) -> std::io::Result<Config> {
-    match toml::from_str(&std::fs::read_to_string(path)?) {
-        Ok(config) => Ok(config),
-        Err(err) => {
-            println!("[ERROR] Failed to parse config: {err}");
-            Err(std::io::ErrorKind::Other.into())
+/// Load and validate the config at `path`.
+pub fn from_path(path: &str) -> Result<Config, Error> {
+    load(path, None)
+}
+
+/// Load and validate the config at `path` with the named `profile` applied.
+///
+/// `path` may also be a png rendered by fract, which loads the config it was rendered
+/// with at the exact position of the render.
+///
+/// A config may build on other configs and define profiles that are layered on top:
+///
+/// ```toml
+/// extends = "base.toml" # or a list, later configs override earlier ones
+/// zoom = "1e-20"
+///
+/// [profile.draft]
+/// width = 400
+/// height = 400
+///
+/// [profile.final]
+/// ssaa = true
+/// ```
+///
+/// Fields override the configs they extend, and the selected profile overrides
+/// every field. Profiles of the same name are merged across configs in the same
+/// way. Relative paths in `extends` are resolved from the extending config.
+pub fn load(path: &str, profile: Option<&str>) -> Result<Config, Error> {
+    let mut fields = Fields::new();
+    let mut profiles = BTreeMap::<String, Fields>::new();
+    merge_layer(path, &mut Vec::new(), &mut fields, &mut profiles)?;
+
+    if let Some(name) = profile {
+        let Some(profile) = profiles.remove(name) else {
+            let defined = profiles.keys().cloned().collect::<Vec<_>>();
+            return Err(Error::invalid(
+                "profile",
+                format!(
+                    "`{name}` is not defined in {path}, the profiles are [{}]",
+                    defined.join(", ")
+                ),
+            ));
+        };
+        for (key, value) in profile {
+            insert_field(&mut fields, key, value);
         }
     }
+
+    // Bounds are fitted to the final dimensions, then refined by the position and zoom.
+    let mut ordered = fields.iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|(key, _)| match key.as_str() {
+        "bounds" => 1,
+        "x" | "y" => 2,
+        key if ZOOM_KEYS.contains(&key) => 3,
+        _ => 0,
+    });
+    let mut config = Config::default();
+    for (key, (value, location)) in ordered {
+        config
+            .set_value(key, value.clone())
+            .map_err(|err| err.at(location.clone()))?;
+    }
+    config.validate().map_err(|err| {
+        let location = match &err {
+            Error::Invalid { field, .. } => fields
+                .get(field)
+                .or_else(|| match field.as_str() {
+                    "x" | "y" => fields.get("bounds"),
+                    "zoom" => ["magnification", "depth", "bounds"]
+                        .iter()
+                        .find_map(|key| fields.get(*key)),
+                    _ => None,
+                })
+                .map(|(_, l)| l.clone()),
+            _ => None,
+        };
+        match location {
+            Some(location) => err.at(location),
+            None => err,
+        }
+    })?;
+    Ok(config)
+}
+
+/// Field values of a config, with where each was set.
+type Fields = BTreeMap<String, (toml::Value, Location)>;
+
+/// Fields that set the zoom, of which a config may only set one.
+const ZOOM_KEYS: [&str; 3] = ["zoom", "magnification", "depth"];
+
+/// Insert `key` into `fields`, replacing the fields that set the same part of the view.
+fn insert_field(fields: &mut Fields, key: String, value: (toml::Value, Location)) {
+    if key == "bounds" {
+        fields.retain(|key, _| !matches!(key.as_str(), "x" | "y") && !ZOOM_KEYS.contains(&&**key));
+    } else if ZOOM_KEYS.contains(&&*key) {
+        fields.retain(|key, _| key != "bounds" && !ZOOM_KEYS.contains(&&**key));
+    }
+    fields.insert(key, value);
+}
+
+/// Reject a table of `fields` that sets the same part of the view twice.
+fn check_view(
+    fields: &BTreeMap<String, toml::Spanned<toml::Value>>,
+    locate: impl Fn(&toml::Spanned<toml::Value>) -> Location,
+) -> Result<(), Error> {
+    let mut view = fields
+        .iter()
+        .filter(|(key, _)| key.as_str() == "bounds" || ZOOM_KEYS.contains(&key.as_str()));
+    let first = view.next();
+    let second = view.next().or_else(|| {
+        first
+            .filter(|(key, _)| key.as_str() == "bounds")
+            .and_then(|_| fields.get_key_value("x").or(fields.get_key_value("y")))
+    });
+    match (first, second) {
+        (Some((first, _)), Some((second, value))) => {
+            Err(Error::invalid(second, format!("conflicts with `{first}`")).at(locate(value)))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Profiles {
+    #[serde(default)]
+    profile: BTreeMap<String, BTreeMap<String, toml::Spanned<toml::Value>>>,
+}
+
+/// Merge the config at `path`, and every config it extends, into `fields` and `profiles`.
+fn merge_layer(
+    path: &str,
+    stack: &mut Vec<std::path::PathBuf>,
+    fields: &mut Fields,
+    profiles: &mut BTreeMap<String, Fields>,
+) -> Result<(), Error> {
+    let io = |source| Error::Io {
+        path: path.to_string(),
+        source,
+    };
+    let parse = |source| Error::Parse {
+        path: path.to_string(),
+        source,
+    };
+    let toml = if is_png(path) {
+        read_png(path)?
+    } else {
+        std::fs::read_to_string(path).map_err(io)?
+    };
+    let canonical = std::fs::canonicalize(path).map_err(io)?;
+    let mut layer =
+        toml::from_str::<BTreeMap<String, toml::Spanned<toml::Value>>>(&toml).map_err(parse)?;
+    let locate = |value: &toml::Spanned<toml::Value>| Location::new(path, &toml, value.span());
+
+    if let Some(extends) = layer.remove("extends") {
+        let parents = match extends.get_ref() {
+            toml::Value::String(parent) => vec![parent.as_str()],
+            toml::Value::Array(parents) => parents
+                .iter()
+                .map(|parent| parent.as_str())
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+        if parents.is_empty() {
+            return Err(
+                Error::invalid("extends", "expected a path or a list of paths")
+                    .at(locate(&extends)),
+            );
+        }
+
+        stack.push(canonical);
+        for parent in parents {
+            let parent = canonical_dir(stack).join(parent);
+            let parent = parent.to_string_lossy();
+            if std::fs::canonicalize(&*parent).is_ok_and(|parent| stack.contains(&parent)) {
+                return Err(
+                    Error::invalid("extends", format!("extending {parent} forms a cycle"))
+                        .at(locate(&extends)),
+                );
+            }
+            merge_layer(&parent, stack, fields, profiles)?;
+        }
+        stack.pop();
+    }
+
+    if layer.remove("profile").is_some() {
+        let layer = toml::from_str::<Profiles>(&toml).map_err(parse)?;
+        for (name, profile) in layer.profile {
+            check_view(&profile, locate)?;
+            let merged = profiles.entry(name).or_default();
+            for (key, value) in profile {
+                let location = locate(&value);
+                insert_field(merged, key, (value.into_inner(), location));
+            }
+        }
+    }
+
+    check_view(&layer, locate)?;
+    for (key, value) in layer {
+        let location = locate(&value);
+        insert_field(fields, key, (value.into_inner(), location));
+    }
+    Ok(())
+}
+
+fn is_png(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+}
+
+/// Recover the config toml from the metadata of the png at `path`, moved to the
+/// position the png was rendered at.
+fn read_png(path: &str) -> Result<String, Error> {
+    let io = |source| Error::Io {
+        path: path.to_string(),
+        source,
+    };
+    let metadata = crate::encoder::read_png_metadata(path).map_err(io)?;
+    let text = |keyword: &str| {
+        metadata
+            .iter()
+            .find(|(k, _)| k == keyword)
+            .map(|(_, text)| text.as_str())
+    };
+    let Some(config) = text(PNG_CONFIG_KEYWORD) else {
+        return Err(io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "png has no config metadata",
+        )));
+    };
+
+    let parse = |source| Error::Parse {
+        path: path.to_string(),
+        source,
+    };
+    let mut config = toml::from_str::<toml::Table>(config).map_err(parse)?;
+    if let Some(position) = text(PNG_POSITION_KEYWORD) {
+        config.extend(toml::from_str::<toml::Table>(position).map_err(parse)?);
+    }
+    Ok(config.to_string())
+}
+
+/// Directory of the config on top of `stack`.
+fn canonical_dir(stack: &[std::path::PathBuf]) -> &std::path::Path {
+    stack
+        .last()
+        .and_then(|path| path.parent())
+        .unwrap_or(std::path::Path::new("."))
 }
 
 pub fn write_to(config: &Config, path: &str) -> std::io::Result<()> {
     match toml::to_string(config) {
         Ok(config) => std::fs::write(path, config),
         Err(err) => {
-            println!("[ERROR] Failed to parse config: {err}");
+            eprintln!("[ERROR] Failed to parse config: {err}");
             Err(std::io::ErrorKind::Other.into())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use malachite::base::{num::conversion::traits::RoundingFrom, rounding_modes::RoundingMode};
+
+    fn float(field: &str) -> Float {
+        float_from_str(field).unwrap()
+    }
+
+    fn approx(field: &str) -> f64 {
+        f64::rounding_from(&float(field), RoundingMode::Nearest).0
+    }
+
+    fn set(config: &mut Config, assignments: &[&str]) {
+        for assignment in assignments {
+            config.set(assignment).unwrap();
+        }
+    }
+
+    #[test]
+    fn magnification_divides_default_zoom() {
+        for (magnification, bits) in [("1", 50), ("4", 50), ("1e421", 1400), ("2^-3000", 50)] {
+            let mut config = Config::default();
+            set(&mut config, &[&format!("magnification={magnification}")]);
+            // `zoom * m` only differs from 2 by the rounding of the division.
+            let error = float(&config.zoom) * float(magnification) - Float::from(2u32);
+            assert!(
+                error
+                    .get_exponent()
+                    .is_none_or(|e| (e as i64) < 2 - bits as i64),
+                "magnification {magnification} gave zoom {}",
+                config.zoom
+            );
+        }
+    }
+
+    #[test]
+    fn depth_halves_zoom_per_octave() {
+        for (depth, expected) in [("0", 1), ("1", 0), ("100", -99), ("-3", 4)] {
+            let mut config = Config::default();
+            set(&mut config, &[&format!("depth={depth}")]);
+            assert_eq!(
+                float(&config.zoom),
+                Float::from(1u32) << expected,
+                "{depth}"
+            );
+        }
+
+        let mut config = Config::default();
+        config.set_value("depth", toml::Value::Integer(3)).unwrap();
+        assert_eq!(float(&config.zoom), Float::from(0.25));
+        set(&mut config, &["depth=2.5"]);
+        assert!((approx(&config.zoom) - (-1.5f64).exp2()).abs() < 1e-15);
+    }
+
+    #[test]
+    fn bounds_fit_rotated_view() {
+        let mut config = Config::default();
+        set(
+            &mut config,
+            &[
+                "width=800",
+                "height=400",
+                r#"bounds={min_x="-2",min_y="-0.5",max_x="0",max_y="0.5"}"#,
+            ],
+        );
+        assert_eq!(float(&config.x), Float::from(-1.0));
+        assert_eq!(float(&config.y), Float::from(0u32));
+        assert_eq!(float(&config.zoom), Float::from(0.5));
+
+        // The box is refitted, standing upright at 90 degrees and diagonal at 45.
+        for (rotation, zoom) in [
+            (90.0, 1.0),
+            (-90.0, 1.0),
+            (180.0, 0.5),
+            (45.0, 1.5 / 2f64.sqrt()),
+        ] {
+            set(&mut config, &[&format!("rotation={rotation:?}")]);
+            assert_eq!(float(&config.x), Float::from(-1.0));
+            assert!((approx(&config.zoom) - zoom).abs() < 1e-12, "{rotation}");
+        }
+
+        // Until the view is set otherwise.
+        set(&mut config, &["zoom=\"3\"", "rotation=0.0"]);
+        assert_eq!(float(&config.zoom), Float::from(3u32));
+    }
+
+    #[test]
+    fn invalid_views_are_errors() {
+        for assignment in [
+            "magnification=0",
+            "magnification=-2",
+            "magnification=abc",
+            "magnification=0x",
+            "depth=abc",
+            "depth=inf",
+            r#"bounds={min_x="0",min_y="0",max_x="-1",max_y="1"}"#,
+            r#"bounds={min_x="0",min_y="0",max_x="1",max_y="0"}"#,
+            r#"bounds={min_x="0",min_y="0",max_x="1",max_y="x"}"#,
+            r#"bounds={min_x="0",min_y="0",max_x="1"}"#,
+            "bounds=1",
+        ] {
+            assert!(Config::default().set(assignment).is_err(), "{assignment}");
+        }
+    }
+
+    /// Directory holding a config file for every `(path, toml)` in `files`.
+    fn configs(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, toml) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, toml).unwrap();
+        }
+        dir
+    }
+
+    fn load_in(
+        dir: &tempfile::TempDir,
+        path: &str,
+        profile: Option<&str>,
+    ) -> Result<Config, Error> {
+        load(&dir.path().join(path).to_string_lossy(), profile)
+    }
+
+    #[test]
+    fn extends_merges_layers() {
+        let dir = configs(&[
+            (
+                "parts/base.toml",
+                "iterations = 500\nwidth = 300\nx = \"-0.5\"\nzoom = \"1e-5\"",
+            ),
+            (
+                "parts/mid.toml",
+                "extends = \"base.toml\"\nwidth = 400\ncolor_scale = 3.0",
+            ),
+            ("other.toml", "width = 100\nheight = 200\ndepth = 10"),
+            (
+                "top.toml",
+                "extends = [\"parts/mid.toml\", \"other.toml\"]\niterations = 800",
+            ),
+        ]);
+
+        let config = load_in(&dir, "parts/mid.toml", None).unwrap();
+        assert_eq!((config.iterations, config.width), (500, 400));
+        assert_eq!((config.x.as_str(), config.zoom.as_str()), ("-0.5", "1e-5"));
+
+        // Later configs override earlier ones, and the extending config overrides both.
+        let config = load_in(&dir, "top.toml", None).unwrap();
+        assert_eq!(config.iterations, 800);
+        assert_eq!((config.width, config.height), (100, 200));
+        assert_eq!(config.color_scale, 3.0);
+        assert_eq!(config.x, "-0.5");
+        // The depth replaces the zoom it was layered over.
+        assert_eq!(float(&config.zoom), Float::from(1u32) >> 9);
+    }
+
+    #[test]
+    fn profiles_override_every_layer() {
+        let dir = configs(&[
+            (
+                "base.toml",
+                "ssaa = false\n\
+                     [profile.draft]\nwidth = 100\niterations = 100\n\
+                     [profile.final]\nssaa = true",
+            ),
+            (
+                "top.toml",
+                "extends = \"base.toml\"\nwidth = 800\nheight = 600\nzoom = \"0.5\"\n\
+                     [profile.draft]\niterations = 200\ndepth = 3",
+            ),
+        ]);
+
+        let config = load_in(&dir, "top.toml", None).unwrap();
+        assert_eq!(
+            (config.width, config.iterations, config.ssaa),
+            (800, 10_000, false)
+        );
+        assert_eq!(config.zoom, "0.5");
+
+        // A profile in an extended config still overrides the extending config, and
+        // profiles of the same name are merged in the order of the configs.
+        let config = load_in(&dir, "top.toml", Some("draft")).unwrap();
+        assert_eq!((config.width, config.height), (100, 600));
+        assert_eq!(config.iterations, 200);
+        assert_eq!(float(&config.zoom), Float::from(0.25));
+
+        let config = load_in(&dir, "top.toml", Some("final")).unwrap();
+        assert_eq!((config.width, config.ssaa), (800, true));
+
+        let err = load_in(&dir, "top.toml", Some("preview")).err().unwrap();
+        assert!(
+            matches!(&err, Error::Invalid { field, .. } if field == "profile"),
+            "{err}"
+        );
+        assert!(err.to_string().contains("[draft, final]"), "{err}");
+    }
+
+    #[test]
+    fn extends_cycles_are_errors() {
+        let dir = configs(&[
+            ("self.toml", "extends = \"self.toml\""),
+            ("a.toml", "extends = \"b.toml\"\nwidth = 100"),
+            ("b.toml", "extends = \"./c.toml\""),
+            ("c.toml", "extends = [\"base.toml\", \"a.toml\"]"),
+            ("base.toml", "height = 100"),
+            ("diamond.toml", "extends = [\"left.toml\", \"right.toml\"]"),
+            ("left.toml", "extends = \"base.toml\"\nwidth = 200"),
+            ("right.toml", "extends = \"base.toml\"\nwidth = 300"),
+            ("missing.toml", "extends = \"none.toml\""),
+        ]);
+
+        for path in ["self.toml", "a.toml", "b.toml", "c.toml"] {
+            let err = load_in(&dir, path, None).err().unwrap();
+            assert!(
+                matches!(&err, Error::Invalid { field, .. } if field == "extends"),
+                "{path}: {err}"
+            );
+            assert!(err.to_string().contains("forms a cycle"), "{path}: {err}");
+            assert!(err.span().is_some(), "{path}: {err}");
+        }
+
+        // Extending the same config twice without a cycle is fine.
+        let config = load_in(&dir, "diamond.toml", None).unwrap();
+        assert_eq!((config.width, config.height), (300, 100));
+
+        let err = load_in(&dir, "missing.toml", None).err().unwrap();
+        assert!(matches!(err, Error::Io { .. }), "{err}");
+    }
+}
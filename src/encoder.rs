@@ -1,42 +1,488 @@
-/// Encodes a series of png images into an mp4 video.
+use crate::config::Position;
+use std::{
+    io::Write,
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+/// Consumes the frames of a video render.
+///
+/// Frames are expected in the BGRA byte order produced by
+/// [`crate::pipeline::Pipeline::read_output_buffer_bytes`].
+pub trait FrameEncoder {
+    fn render_frame(&mut self, frame_buffer: &[u8]) -> std::io::Result<()>;
+
+    /// Set the png text chunks of the next frames, for encoders that save frames as
+    /// pngs. See [`crate::pipeline::Pipeline::png_metadata`].
+    fn set_metadata(&mut self, _metadata: Vec<(String, String)>) {}
+
+    /// Write the remainder of the video.
+    fn finish(self) -> std::io::Result<()>;
+}
+
+/// Options forwarded to `ffmpeg` when encoding a video.
+#[derive(Debug, Clone)]
+pub struct EncoderOptions {
+    /// Name or path of the `ffmpeg` executable.
+    ///
+    /// Any program that consumes raw frames from stdin may be substituted.
+    pub program: String,
+    pub codec: String,
+    pub crf: u32,
+    pub preset: String,
+    pub pix_fmt: String,
+    /// Save every frame as a png in `data_dir/frames`.
+    pub keep_frames: bool,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        Self {
+            program: "ffmpeg".to_string(),
+            codec: "libx264".to_string(),
+            crf: 23,
+            preset: "medium".to_string(),
+            pix_fmt: "yuv420p".to_string(),
+            keep_frames: false,
+        }
+    }
+}
+
+/// Streams raw frames to an `ffmpeg` process to encode a video.
 pub struct Encoder {
     data_dir: String,
+    program: String,
     width: usize,
     height: usize,
-    fps: usize,
     frame: usize,
+    keep_frames: bool,
+    metadata: Vec<(String, String)>,
+    child: Child,
+    stdin: Option<std::io::BufWriter<ChildStdin>>,
 }
 
 impl Encoder {
-    pub fn new(data_dir: String, width: usize, height: usize, fps: usize) -> Self {
-        _ = std::fs::create_dir_all(format!("{data_dir}/frames"));
-        Self {
+    /// Spawn `options.program` encoding into `output`.
+    pub fn new(
+        output: &str,
+        data_dir: String,
+        width: usize,
+        height: usize,
+        fps: usize,
+        options: &EncoderOptions,
+    ) -> std::io::Result<Self> {
+        if options.keep_frames {
+            std::fs::create_dir_all(format!("{data_dir}/frames"))?;
+        }
+
+        let size = format!("{width}x{height}");
+        let fps = format!("{fps}");
+        let crf = format!("{}", options.crf);
+        #[rustfmt::skip]
+        let mut child = Command::new(&options.program)
+            .args([
+                "-hide_banner",
+                "-loglevel", "error",
+                "-y",
+                "-f", "rawvideo",
+                "-pix_fmt", "bgra",
+                "-s", &size,
+                "-framerate", &fps,
+                "-i", "-",
+                "-c:v", &options.codec,
+                "-preset", &options.preset,
+                "-crf", &crf,
+                "-pix_fmt", &options.pix_fmt,
+                "-an",
+                output,
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                std::io::Error::new(
+                    err.kind(),
+                    format!("failed to spawn `{}`: {err}", options.program),
+                )
+            })?;
+        let stdin = child.stdin.take().map(std::io::BufWriter::new);
+
+        Ok(Self {
             data_dir,
+            program: options.program.clone(),
             width,
             height,
-            fps,
             frame: 0,
+            keep_frames: options.keep_frames,
+            metadata: Vec::new(),
+            child,
+            stdin,
+        })
+    }
+
+    /// Stream the first `frames` png frames previously saved in `data_dir/frames`.
+    ///
+    /// Used to resume a render: the frames are not saved again.
+    pub fn replay_frames(&mut self, frames: usize) -> std::io::Result<()> {
+        for i in 0..frames {
+            let path = format!("{}/frames/{i}.png", self.data_dir);
+            let frame = read_frame(&path, self.width, self.height)?;
+
+            self.write(&frame)?;
+            self.frame += 1;
+        }
+        Ok(())
+    }
+
+    /// Directory the png frames are saved to, if they are kept.
+    pub fn frame_path(&self) -> Option<&str> {
+        self.keep_frames.then_some(&*self.data_dir)
+    }
+
+    fn write(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                format!("`{}` is no longer encoding", self.program),
+            ));
+        };
+        stdin.write_all(frame).map_err(|err| self.exited(err))
+    }
+
+    // The pipe most likely broke because the encoder exited, which is more
+    // useful to report than the write error itself.
+    fn exited(&mut self, err: std::io::Error) -> std::io::Error {
+        self.stdin = None;
+        match self.child.wait() {
+            Ok(status) => std::io::Error::new(
+                err.kind(),
+                format!(
+                    "`{}` exited with {status} while encoding frame {}: {err}",
+                    self.program, self.frame
+                ),
+            ),
+            Err(_) => err,
+        }
+    }
+}
+
+impl FrameEncoder for Encoder {
+    /// Stream `frame_buffer` to the encoder, optionally saving it to a png in `data_dir`.
+    fn render_frame(&mut self, frame_buffer: &[u8]) -> std::io::Result<()> {
+        if frame_buffer.len() != self.width * self.height * 4 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "frame {} has {} bytes, expected {}",
+                    self.frame,
+                    frame_buffer.len(),
+                    self.width * self.height * 4
+                ),
+            ));
+        }
+
+        if self.keep_frames {
+            let output = format!("{}/frames/{}.png", self.data_dir, self.frame);
+            write_atomically(&output, |partial| {
+                png_with_metadata(
+                    partial,
+                    frame_buffer,
+                    self.width,
+                    self.height,
+                    true,
+                    &self.metadata,
+                )
+            })?;
+        }
+
+        self.write(frame_buffer)?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn set_metadata(&mut self, metadata: Vec<(String, String)>) {
+        self.metadata = metadata;
+    }
+
+    /// Close the stream and wait for the encoder to write the video.
+    fn finish(mut self) -> std::io::Result<()> {
+        if let Some(mut stdin) = self.stdin.take()
+            && let Err(err) = stdin.flush()
+        {
+            return Err(self.exited(err));
+        }
+
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!(
+                "`{}` exited with {status}",
+                self.program
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Saves every frame as a png in `dir/frames`, numbered from the first frame's index.
+///
+/// Used by distributed renders, which are assembled into a video once every frame
+/// has been written.
+pub struct PngSequence {
+    dir: String,
+    width: usize,
+    height: usize,
+    frame: usize,
+    metadata: Vec<(String, String)>,
+}
+
+impl PngSequence {
+    pub fn new(dir: &str, width: usize, height: usize, start: usize) -> std::io::Result<Self> {
+        std::fs::create_dir_all(format!("{dir}/frames"))?;
+        Ok(Self {
+            dir: dir.to_string(),
+            width,
+            height,
+            frame: start,
+            metadata: Vec::new(),
+        })
+    }
+}
+
+impl FrameEncoder for PngSequence {
+    fn render_frame(&mut self, frame_buffer: &[u8]) -> std::io::Result<()> {
+        let output = format!("{}/frames/{}.png", self.dir, self.frame);
+        write_atomically(&output, |partial| {
+            png_with_metadata(
+                partial,
+                frame_buffer,
+                self.width,
+                self.height,
+                true,
+                &self.metadata,
+            )
+        })?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn set_metadata(&mut self, metadata: Vec<(String, String)>) {
+        self.metadata = metadata;
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Describes the frames in a directory shared by distributed renders.
+///
+/// Renders only share a directory if their manifests match, so every frame in it
+/// belongs to the same video.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FrameManifest {
+    pub frames: usize,
+    pub fps: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Serialized config of the render.
+    pub config: String,
+    /// Exact positions of the first and last frames.
+    pub start: Position,
+    pub end: Position,
+}
+
+impl FrameManifest {
+    pub fn from_dir(dir: &str) -> std::io::Result<Self> {
+        toml::from_str(&std::fs::read_to_string(format!("{dir}/frames.toml"))?)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    /// Write the manifest to `dir`, or check that it matches the one already there.
+    pub fn write_to(&self, dir: &str) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        match Self::from_dir(dir) {
+            Ok(manifest) if manifest == *self => Ok(()),
+            Ok(manifest) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{dir} holds frames of a different render, with {}",
+                    manifest.difference(self)
+                ),
+            )),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => std::fs::write(
+                format!("{dir}/frames.toml"),
+                toml::to_string(self).map_err(std::io::Error::other)?,
+            ),
+            Err(err) => Err(err),
         }
     }
 
-    pub fn frame_path(&self) -> &str {
-        &self.data_dir
+    /// Description of the first field that differs from `other`, as it is in `self`.
+    fn difference(&self, other: &Self) -> String {
+        if (self.frames, self.fps) != (other.frames, other.fps) {
+            format!("{} frames @ {}fps", self.frames, self.fps)
+        } else if (self.width, self.height) != (other.width, other.height) {
+            format!("a size of {}x{}", self.width, self.height)
+        } else if self.config != other.config {
+            format!("a different config:\n{}", self.config)
+        } else if self.start != other.start {
+            format!("a start position of {:?}", self.start)
+        } else {
+            format!("an end position of {:?}", self.end)
+        }
+    }
+
+    /// Indices of the frames that are not in `dir/frames` yet.
+    pub fn missing_frames(&self, dir: &str) -> Vec<usize> {
+        (0..self.frames)
+            .filter(|i| !std::path::Path::new(&format!("{dir}/frames/{i}.png")).exists())
+            .collect()
+    }
+}
+
+/// Encodes an animated png in-process.
+pub struct ApngEncoder {
+    writer: png::Writer<std::io::BufWriter<std::fs::File>>,
+}
+
+impl ApngEncoder {
+    /// Create `output` for an animation of `frames` frames that loops forever.
+    pub fn new(
+        output: &str,
+        width: usize,
+        height: usize,
+        fps: usize,
+        frames: usize,
+    ) -> std::io::Result<Self> {
+        let file = std::fs::File::create(output)?;
+        let output = std::io::BufWriter::new(file);
+        let mut encoder = png::Encoder::new(output, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames as u32, 0)?;
+        encoder.set_frame_delay(1, frame_rate(fps)?)?;
+        let writer = encoder.write_header()?;
+
+        Ok(Self { writer })
+    }
+}
+
+impl FrameEncoder for ApngEncoder {
+    fn render_frame(&mut self, frame_buffer: &[u8]) -> std::io::Result<()> {
+        self.writer.write_image_data(&bgra_to_rgba(frame_buffer))?;
+        Ok(())
     }
 
-    /// Save `frame_buffer` to a png in `data_dir`.
-    pub fn render_frame(&mut self, frame_buffer: &[u8]) -> std::io::Result<()> {
-        let output = format!("{}/frames/{}.png", self.data_dir, self.frame);
-        png(&output, frame_buffer, self.width, self.height, true)?;
+    fn finish(self) -> std::io::Result<()> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Encodes a gif in-process, quantizing every frame to its own 256 color palette.
+pub struct GifEncoder {
+    encoder: gif::Encoder<std::io::BufWriter<std::fs::File>>,
+    width: u16,
+    height: u16,
+    fps: usize,
+    frame: usize,
+}
+
+impl GifEncoder {
+    /// Create `output` for an animation that loops forever.
+    pub fn new(output: &str, width: usize, height: usize, fps: usize) -> std::io::Result<Self> {
+        let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{width}x{height} exceeds the maximum gif dimensions"),
+            ));
+        };
+        frame_rate(fps)?;
+
+        let file = std::fs::File::create(output)?;
+        let output = std::io::BufWriter::new(file);
+        let mut encoder = gif::Encoder::new(output, w, h, &[]).map_err(std::io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(std::io::Error::other)?;
+
+        Ok(Self {
+            encoder,
+            width: w,
+            height: h,
+            fps,
+            frame: 0,
+        })
+    }
+}
+
+impl FrameEncoder for GifEncoder {
+    fn render_frame(&mut self, frame_buffer: &[u8]) -> std::io::Result<()> {
+        let mut rgba = bgra_to_rgba(frame_buffer);
+        let mut frame = gif::Frame::from_rgba_speed(self.width, self.height, &mut rgba, 10);
+
+        // Gif delays are in hundredths of a second, so distribute the rounding
+        // error across frames to keep the total duration exact.
+        let end = ((self.frame + 1) * 100 + self.fps / 2) / self.fps;
+        let start = (self.frame * 100 + self.fps / 2) / self.fps;
+        frame.delay = (end - start) as u16;
+
+        self.encoder
+            .write_frame(&frame)
+            .map_err(std::io::Error::other)?;
         self.frame += 1;
         Ok(())
     }
 
-    /// Generate an mp4 video from the rendered frames in `data_dir`.
-    pub fn finish(self, output: &str) -> std::io::Result<()> {
-        ffmpeg(&self.data_dir, output, self.fps)
+    fn finish(self) -> std::io::Result<()> {
+        self.encoder
+            .into_inner()
+            .map_err(std::io::Error::other)?
+            .flush()
     }
 }
 
+/// Swap the blue and red channels of a frame read back from the gpu.
+pub fn bgra_to_rgba(frame: &[u8]) -> Vec<u8> {
+    frame
+        .chunks_exact(4)
+        .flat_map(|bgr| [bgr[2], bgr[1], bgr[0], bgr[3]])
+        .collect()
+}
+
+fn frame_rate(fps: usize) -> std::io::Result<u16> {
+    match u16::try_from(fps) {
+        Ok(fps) if fps > 0 => Ok(fps),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid frame rate: {fps}"),
+        )),
+    }
+}
+
+/// Decode a `width`x`height` png frame written by [`png`] back into BGRA.
+pub fn read_frame(path: &str, width: usize, height: usize) -> std::io::Result<Vec<u8>> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut reader = png::Decoder::new(file).read_info()?;
+    let info = reader.info();
+    if info.width as usize != width
+        || info.height as usize != height
+        || info.color_type != png::ColorType::Rgba
+        || info.bit_depth != png::BitDepth::Eight
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{path} does not match the {width}x{height} render"),
+        ));
+    }
+
+    let mut frame = vec![0; reader.output_buffer_size().unwrap()];
+    reader.next_frame(&mut frame)?;
+    for pixel in frame.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    Ok(frame)
+}
+
 // Fast png encoding using the rust `png` crate.
 pub fn png(
     output: &str,
@@ -44,45 +490,212 @@ pub fn png(
     width: usize,
     height: usize,
     flip_channels: bool,
+) -> std::io::Result<()> {
+    png_with_metadata(output, frame, width, height, flip_channels, &[])
+}
+
+/// Call `write` with `{output}.part` and move the result to `output` once it succeeds.
+///
+/// `--resume` and `assemble` trust every saved frame, so an interrupted write must
+/// never leave a truncated png behind.
+fn write_atomically(
+    output: &str,
+    write: impl FnOnce(&str) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let partial = format!("{output}.part");
+    write(&partial)?;
+    std::fs::rename(partial, output)
+}
+
+/// [`png`] with a utf-8 text chunk for every `(keyword, text)` in `metadata`.
+pub fn png_with_metadata(
+    output: &str,
+    frame: &[u8],
+    width: usize,
+    height: usize,
+    flip_channels: bool,
+    metadata: &[(String, String)],
 ) -> std::io::Result<()> {
     let file = std::fs::File::create(output)?;
     let output = std::io::BufWriter::new(file);
     let mut encoder = png::Encoder::new(output, width as u32, height as u32);
     encoder.set_color(png::ColorType::Rgba);
     encoder.set_depth(png::BitDepth::Eight);
-    let mut writer = encoder.write_header().unwrap();
+    for (keyword, text) in metadata {
+        encoder.add_itxt_chunk(keyword.clone(), text.clone())?;
+    }
+    let mut writer = encoder.write_header()?;
 
     if flip_channels {
-        let frame: Vec<u8> = frame
-            .chunks_exact(4)
-            .flat_map(|bgr| [bgr[2], bgr[1], bgr[0], bgr[3]])
-            .collect();
-        writer.write_image_data(&frame).unwrap();
+        writer.write_image_data(&bgra_to_rgba(frame))?;
     } else {
-        writer.write_image_data(frame).unwrap();
+        writer.write_image_data(frame)?;
     }
 
+    // Dropping the writer would flush it, but silently discard a failure.
+    writer.finish()?;
     Ok(())
 }
 
-fn ffmpeg(root: &str, output: &str, fps: usize) -> std::io::Result<()> {
-    let fps = &format!("{fps}");
-    let frames = &format!("{root}/frames/%d.png");
-    #[rustfmt::skip]
-    std::process::Command::new("ffmpeg")
-        .args([
-            "-framerate", fps,
-            "-i", frames,
-            "-c:v", "libx264",
-            "-preset", "medium",
-            "-crf", "23",
-            "-pix_fmt", "yuv420p",
-            "-c:a", "aac",
-            "-b:a", "192k",
-            output,
-        ])
-            .spawn()
-            .unwrap()
-            .wait()?;
-    Ok(())
+/// Read the utf-8 text chunks of the png at `path` as `(keyword, text)` pairs.
+pub fn read_png_metadata(path: &str) -> std::io::Result<Vec<(String, String)>> {
+    let file = std::fs::File::open(path)?;
+    let reader = png::Decoder::new(std::io::BufReader::new(file)).read_info()?;
+    reader
+        .info()
+        .utf8_text
+        .iter()
+        .map(|chunk| Ok((chunk.keyword.clone(), chunk.get_text()?)))
+        .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{os::unix::fs::PermissionsExt, path::Path};
+
+    /// Directory holding a stand-in encoder that saves at most `limit` bytes of its stdin
+    /// to `dir/stdin` and their count to `dir/count`, then exits with `status`.
+    fn stand_in(limit: usize, status: i32) -> (tempfile::TempDir, EncoderOptions) {
+        let dir = tempfile::tempdir().unwrap();
+
+        let program = dir.path().join("encoder.sh");
+        let script = format!(
+            "#!/bin/sh\n\
+             head -c {limit} > '{0}/stdin'\n\
+             wc -c < '{0}/stdin' > '{0}/count'\n\
+             exit {status}\n",
+            dir.path().display()
+        );
+        std::fs::write(&program, script).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let options = EncoderOptions {
+            program: program.to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        (dir, options)
+    }
+
+    fn encoder(dir: &Path, width: usize, height: usize, options: &EncoderOptions) -> Encoder {
+        let data_dir = dir.to_string_lossy().into_owned();
+        Encoder::new("out.mp4", data_dir, width, height, 30, options).unwrap()
+    }
+
+    fn frame(width: usize, height: usize, seed: usize) -> Vec<u8> {
+        (0..width * height * 4)
+            .map(|byte| (byte * 7 + seed) as u8)
+            .collect()
+    }
+
+    fn count(dir: &Path) -> usize {
+        let count = std::fs::read_to_string(dir.join("count")).unwrap();
+        count.trim().parse().unwrap()
+    }
+
+    #[test]
+    fn streams_raw_frames() {
+        let (temp, options) = stand_in(usize::MAX, 0);
+        let dir = temp.path();
+        let frames: Vec<_> = (0..4).map(|i| frame(3, 2, i)).collect();
+
+        let mut encoder = encoder(dir, 3, 2, &options);
+        for frame in &frames {
+            encoder.render_frame(frame).unwrap();
+        }
+        encoder.finish().unwrap();
+
+        assert_eq!(count(dir), 3 * 2 * 4 * 4);
+        assert_eq!(std::fs::read(dir.join("stdin")).unwrap(), frames.concat());
+    }
+
+    #[test]
+    fn reports_failed_exit() {
+        let (temp, options) = stand_in(usize::MAX, 3);
+        let dir = temp.path();
+
+        let mut encoder = encoder(dir, 3, 2, &options);
+        encoder.render_frame(&frame(3, 2, 0)).unwrap();
+        let err = encoder.finish().unwrap_err();
+
+        assert_eq!(count(dir), 3 * 2 * 4);
+        assert!(
+            err.to_string().ends_with("exited with exit status: 3"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn reports_exit_while_encoding() {
+        // Frames much larger than the pipe buffer, so that writing fails once the
+        // stand-in stops reading.
+        let (temp, options) = stand_in(1000, 3);
+        let dir = temp.path();
+        let frame = frame(512, 512, 0);
+
+        let mut encoder = encoder(dir, 512, 512, &options);
+        let err = encoder.render_frame(&frame).unwrap_err();
+        assert_eq!(count(dir), 1000);
+        assert_eq!(std::fs::read(dir.join("stdin")).unwrap(), frame[..1000]);
+        assert!(
+            err.to_string()
+                .contains("exited with exit status: 3 while encoding frame 0"),
+            "{err}"
+        );
+
+        let err = encoder.render_frame(&frame).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn apng_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.png");
+        let frames: Vec<_> = (0..3).map(|i| frame(3, 2, i)).collect();
+
+        let mut encoder = ApngEncoder::new(output.to_str().unwrap(), 3, 2, 30, 3).unwrap();
+        for frame in &frames {
+            encoder.render_frame(frame).unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let file = std::io::BufReader::new(std::fs::File::open(&output).unwrap());
+        let mut reader = png::Decoder::new(file).read_info().unwrap();
+        let animation = reader.info().animation_control.unwrap();
+        assert_eq!((animation.num_frames, animation.num_plays), (3, 0));
+
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        for frame in &frames {
+            reader.next_frame(&mut decoded).unwrap();
+            let control = reader.info().frame_control.unwrap();
+            assert_eq!((control.delay_num, control.delay_den), (1, 30));
+            assert_eq!(decoded, bgra_to_rgba(frame));
+        }
+    }
+
+    #[test]
+    fn gif_delays_add_up_to_the_duration() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.gif");
+
+        // One second at a frame rate that does not divide a hundredth of a second.
+        let mut encoder = GifEncoder::new(output.to_str().unwrap(), 3, 2, 30).unwrap();
+        for i in 0..30 {
+            encoder.render_frame(&frame(3, 2, i)).unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let file = std::io::BufReader::new(std::fs::File::open(&output).unwrap());
+        let mut decoder = gif::DecodeOptions::new().read_info(file).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays.len(), 30);
+        assert!(
+            delays.iter().all(|&delay| delay == 3 || delay == 4),
+            "{delays:?}"
+        );
+        assert_eq!(delays.iter().sum::<u16>(), 100);
+    }
 }
@@ -0,0 +1,125 @@
+use std::ops::Range;
+
+/// Errors raised while loading and validating a [`crate::config::Config`] or
+/// [`crate::timeline::Timeline`].
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// The file is not valid toml, or a field has the wrong type.
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    /// A field holds a value that cannot be rendered.
+    Invalid {
+        field: String,
+        message: String,
+        location: Option<Location>,
+    },
+}
+
+/// Where an invalid field was found.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub path: String,
+    /// Byte range of the value in the file.
+    pub span: Range<usize>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn new(path: &str, text: &str, span: Range<usize>) -> Self {
+        let before = &text[..span.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Self {
+            path: path.to_string(),
+            span,
+            line,
+            column,
+        }
+    }
+}
+
+impl Error {
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            field: field.to_string(),
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    /// Attach `location` to an invalid field error that does not have one yet.
+    pub fn at(self, location: Location) -> Self {
+        match self {
+            Self::Invalid {
+                field,
+                message,
+                location: None,
+            } => Self::Invalid {
+                field,
+                message,
+                location: Some(location),
+            },
+            err => err,
+        }
+    }
+
+    /// Byte range in the source file that caused the error, if known.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::Io { .. } => None,
+            Self::Parse { source, .. } => source.span(),
+            Self::Invalid { location, .. } => location.as_ref().map(|l| l.span.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {path}: {source}"),
+            Self::Parse { path, source } => write!(f, "failed to parse {path}: {source}"),
+            Self::Invalid {
+                field,
+                message,
+                location: Some(location),
+            } => write!(
+                f,
+                "invalid `{field}` at {}:{}:{}: {message}",
+                location.path, location.line, location.column
+            ),
+            Self::Invalid {
+                field,
+                message,
+                location: None,
+            } => write!(f, "invalid `{field}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match &err {
+            Error::Io { source, .. } => source.kind(),
+            Error::Parse { .. } => std::io::ErrorKind::InvalidData,
+            Error::Invalid { .. } => std::io::ErrorKind::InvalidInput,
+        };
+        std::io::Error::new(kind, err)
+    }
+}
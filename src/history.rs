@@ -0,0 +1,254 @@
+use crate::{config::Config, float_from_str};
+use malachite_float::Float;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Most views kept to undo.
+const MAX_HISTORY: usize = 256;
+
+/// Config of the viewer with its exact position.
+#[derive(Clone)]
+pub struct View {
+    pub x: Float,
+    pub y: Float,
+    pub zoom: Float,
+    pub config: Config,
+}
+
+impl View {
+    /// View of a valid `config`.
+    pub fn from_config(config: Config) -> Self {
+        Self {
+            x: float_from_str(&config.x).expect("config has a valid x"),
+            y: float_from_str(&config.y).expect("config has a valid y"),
+            zoom: float_from_str(&config.zoom).expect("config has a valid zoom"),
+            config,
+        }
+    }
+
+    /// Config with the exact position of the view.
+    pub fn to_config(&self) -> Config {
+        Config {
+            x: self.x.to_string(),
+            y: self.y.to_string(),
+            zoom: self.zoom.to_string(),
+            ..self.config.clone()
+        }
+    }
+}
+
+/// Views navigated away from, and the views undone since.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<View>,
+    redo: Vec<View>,
+}
+
+impl History {
+    /// Record `view` before navigating away from it, forgetting the views undone.
+    pub fn push(&mut self, view: View) {
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.undo.push(view);
+        self.redo.clear();
+    }
+
+    /// Return to the last view, recording `current` to redo.
+    pub fn undo(&mut self, current: View) -> Option<View> {
+        let view = self.undo.pop()?;
+        self.redo.push(current);
+        Some(view)
+    }
+
+    /// Return to the last view undone, recording `current` to undo.
+    pub fn redo(&mut self, current: View) -> Option<View> {
+        let view = self.redo.pop()?;
+        self.undo.push(current);
+        Some(view)
+    }
+}
+
+/// Views saved to numbered slots, persisted to a toml file of configs keyed by slot.
+#[derive(Default)]
+pub struct Bookmarks {
+    /// File the bookmarks are written to, if they are persisted.
+    path: Option<PathBuf>,
+    slots: BTreeMap<String, Config>,
+}
+
+impl Bookmarks {
+    /// Read the bookmarks at `path`, which need not exist yet.
+    ///
+    /// If the file can't be read, the bookmarks are kept in memory so it is not overwritten.
+    pub fn load(path: PathBuf) -> Self {
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                eprintln!("[ERROR] Failed to read bookmarks {}: {err}", path.display());
+                return Self::default();
+            }
+        };
+
+        let slots = match toml::from_str::<BTreeMap<String, Config>>(&text) {
+            Ok(slots) => slots,
+            Err(err) => {
+                eprintln!(
+                    "[ERROR] Failed to parse bookmarks {}: {err}",
+                    path.display()
+                );
+                return Self::default();
+            }
+        };
+        for (slot, config) in &slots {
+            if let Err(err) = config.validate() {
+                eprintln!("[ERROR] Bookmark {slot} in {}: {err}", path.display());
+                return Self::default();
+            }
+        }
+
+        Self {
+            path: Some(path),
+            slots,
+        }
+    }
+
+    pub fn get(&self, slot: u8) -> Option<View> {
+        self.slots
+            .get(&slot.to_string())
+            .map(|config| View::from_config(config.clone()))
+    }
+
+    /// Save `view` to `slot`, writing the bookmarks if they are persisted.
+    pub fn set(&mut self, slot: u8, view: &View) {
+        self.slots.insert(slot.to_string(), view.to_config());
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = toml::to_string(&self.slots)
+            .map_err(std::io::Error::other)
+            .and_then(|text| std::fs::write(path, text));
+        if let Err(err) = result {
+            eprintln!(
+                "[ERROR] Failed to write bookmarks {}: {err}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// View identified by its `zoom`.
+    fn view(zoom: u32) -> View {
+        View::from_config(Config {
+            zoom: zoom.to_string(),
+            ..Config::default()
+        })
+    }
+
+    fn zoom(view: Option<View>) -> Option<Float> {
+        view.map(|view| view.zoom)
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        assert!(zoom(history.undo(view(1))).is_none());
+        assert!(zoom(history.redo(view(1))).is_none());
+
+        history.push(view(1));
+        history.push(view(2));
+        assert_eq!(zoom(history.undo(view(3))), Some(Float::from(2u32)));
+        assert_eq!(zoom(history.undo(view(2))), Some(Float::from(1u32)));
+        assert!(history.undo(view(1)).is_none());
+        assert_eq!(zoom(history.redo(view(1))), Some(Float::from(2u32)));
+        assert_eq!(zoom(history.redo(view(2))), Some(Float::from(3u32)));
+        assert!(history.redo(view(3)).is_none());
+        assert_eq!(zoom(history.undo(view(3))), Some(Float::from(2u32)));
+
+        // Navigating after undoing forgets the views undone.
+        history.push(view(2));
+        assert!(history.redo(view(4)).is_none());
+        assert_eq!(zoom(history.undo(view(4))), Some(Float::from(2u32)));
+        assert_eq!(zoom(history.undo(view(2))), Some(Float::from(1u32)));
+    }
+
+    #[test]
+    fn history_evicts_oldest() {
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY as u32 + 10 {
+            history.push(view(i));
+        }
+
+        let mut current = view(u32::MAX);
+        let mut undone = Vec::new();
+        while let Some(view) = history.undo(current) {
+            undone.push(view.zoom.clone());
+            current = view;
+        }
+        let expected = (10..MAX_HISTORY as u32 + 10).rev().map(Float::from);
+        assert!(undone.into_iter().eq(expected));
+    }
+
+    #[test]
+    fn bookmarks_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.toml");
+
+        // Coordinates with more bits than a `f64`.
+        let mut saved = view(1);
+        saved.x = Float::from(-0.75);
+        saved.x.set_prec(256);
+        saved.x += Float::from(1u32) >> 200;
+        saved.y = float_from_str("0x1.8p-100").unwrap();
+        saved.zoom = Float::from(3u32);
+        saved.zoom.set_prec(128);
+        saved.zoom >>= 180;
+        saved.config.iterations = 5000;
+
+        let mut bookmarks = Bookmarks::load(path.clone());
+        assert!(bookmarks.get(1).is_none());
+        bookmarks.set(1, &saved);
+        bookmarks.set(2, &view(2));
+
+        let bookmarks = Bookmarks::load(path.clone());
+        let loaded = bookmarks.get(1).unwrap();
+        for (loaded, saved) in [
+            (loaded.x, &saved.x),
+            (loaded.y, &saved.y),
+            (loaded.zoom, &saved.zoom),
+        ] {
+            let mut loaded = loaded;
+            loaded.set_prec(saved.get_prec().unwrap());
+            assert_eq!(loaded, *saved);
+        }
+        assert_eq!(loaded.config.iterations, 5000);
+        assert_eq!(bookmarks.get(2).unwrap().zoom, Float::from(2u32));
+        assert!(bookmarks.get(3).is_none());
+    }
+
+    #[test]
+    fn invalid_bookmarks_are_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for (name, text) in [
+            ("malformed.toml", "[1\nzoom = \"2\""),
+            ("unknown.toml", "[1]\nzoom = \"2\"\nspeed = 3"),
+            ("invalid.toml", "[1]\nzoom = \"2\"\n[2]\niterations = 0"),
+            ("nan.toml", "[1]\nx = \"one\""),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, text).unwrap();
+
+            let mut bookmarks = Bookmarks::load(path.clone());
+            assert!(bookmarks.get(1).is_none(), "{name}");
+            bookmarks.set(1, &view(4));
+            assert_eq!(bookmarks.get(1).unwrap().zoom, Float::from(4u32), "{name}");
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), text, "{name}");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+
+/// Frame rendered at a power-of-two zoom level.
+///
+/// Keyframes cover `margin` times the area of a video frame in both dimensions
+/// so that the next, deeper keyframe can be blended in before it fills the frame.
+pub struct Keyframe {
+    /// Zoom level in octaves, where the keyframe zoom is `zoom * margin * 2^-octave`.
+    pub octave: i64,
+    pub pixels: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Keyframe {
+    /// Bilinearly sample the keyframe at `(u, v)`, where `u` is in `[-aspect, aspect]`
+    /// and `v` is in `[-1, 1]` of the keyframe's own view.
+    ///
+    /// Returns `None` if the sample lies outside of the keyframe.
+    fn sample(&self, u: f32, v: f32) -> Option<[f32; 4]> {
+        let aspect = self.width as f32 / self.height as f32;
+        let x = (u / aspect + 1.0) * 0.5 * self.width as f32;
+        let y = (v + 1.0) * 0.5 * self.height as f32;
+        if x < 0.0 || y < 0.0 || x > (self.width - 1) as f32 || y > (self.height - 1) as f32 {
+            return None;
+        }
+
+        let x0 = x.floor() as usize;
+        let y0 = y.floor() as usize;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let fx = x - x0 as f32;
+        let fy = y - y0 as f32;
+
+        let texel = |x: usize, y: usize| -> [f32; 4] {
+            let i = (y * self.width + x) * 4;
+            let p = &self.pixels[i..i + 4];
+            [p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32]
+        };
+        let (a, b, c, d) = (texel(x0, y0), texel(x1, y0), texel(x0, y1), texel(x1, y1));
+        Some(std::array::from_fn(|i| {
+            let top = a[i] + (b[i] - a[i]) * fx;
+            let bottom = c[i] + (d[i] - c[i]) * fx;
+            top + (bottom - top) * fy
+        }))
+    }
+}
+
+/// Synthesize a `width`x`height` frame at `octave` from the keyframes that surround it.
+///
+/// `outer` must be the keyframe at `octave.floor()` and `inner` the keyframe one
+/// octave deeper. Both are exponentially scaled to the frame's zoom, then cross-blended
+/// by the fractional octave wherever `inner` covers the frame.
+pub fn interpolate(
+    outer: &Keyframe,
+    inner: &Keyframe,
+    octave: f64,
+    margin: f64,
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    debug_assert_eq!(outer.octave + 1, inner.octave);
+    let blend = (octave - outer.octave as f64) as f32;
+    let outer_scale = ((outer.octave as f64 - octave).exp2() / margin) as f32;
+    let inner_scale = ((inner.octave as f64 - octave).exp2() / margin) as f32;
+    let aspect = width as f32 / height as f32;
+
+    let mut frame = vec![0; width * height * 4];
+    frame
+        .par_chunks_mut(width * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let v = y as f32 / height as f32 * 2.0 - 1.0;
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let u = (x as f32 / width as f32 * 2.0 - 1.0) * aspect;
+                let outer = outer
+                    .sample(u * outer_scale, v * outer_scale)
+                    .unwrap_or_default();
+                let color = match inner.sample(u * inner_scale, v * inner_scale) {
+                    Some(inner) => {
+                        std::array::from_fn(|i| outer[i] + (inner[i] - outer[i]) * blend)
+                    }
+                    None => outer,
+                };
+                for (dst, src) in pixel.iter_mut().zip(color) {
+                    *dst = src.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        });
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keyframe at `octave` with a distinct color in every pixel.
+    fn keyframe(octave: i64, width: usize, height: usize, seed: usize) -> Keyframe {
+        let pixels = (0..width * height * 4)
+            .map(|byte| ((byte / 4) * 7 + (byte % 4) * 50 + seed) as u8)
+            .collect();
+        Keyframe {
+            octave,
+            pixels,
+            width,
+            height,
+        }
+    }
+
+    fn solid(octave: i64, width: usize, height: usize, value: u8) -> Keyframe {
+        Keyframe {
+            octave,
+            pixels: vec![value; width * height * 4],
+            width,
+            height,
+        }
+    }
+
+    /// The `width`x`height` centre of `keyframe`.
+    fn crop(keyframe: &Keyframe, width: usize, height: usize) -> Vec<u8> {
+        let (x0, y0) = ((keyframe.width - width) / 2, (keyframe.height - height) / 2);
+        (y0..y0 + height)
+            .flat_map(|y| {
+                let start = (y * keyframe.width + x0) * 4;
+                keyframe.pixels[start..start + width * 4].iter().copied()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ends_reproduce_keyframes() {
+        for (margin, width, height) in [(1.0, 8, 4), (2.0, 8, 6), (2.0, 6, 8)] {
+            let (kw, kh) = (width * margin as usize, height * margin as usize);
+            let outer = keyframe(3, kw, kh, 0);
+            let inner = keyframe(4, kw, kh, 100);
+
+            let frame = interpolate(&outer, &inner, 3.0, margin, width, height);
+            assert_eq!(frame, crop(&outer, width, height), "margin {margin}");
+            let frame = interpolate(&outer, &inner, 4.0, margin, width, height);
+            assert_eq!(frame, crop(&inner, width, height), "margin {margin}");
+        }
+    }
+
+    #[test]
+    fn blends_where_inner_covers() {
+        let outer = solid(0, 16, 16, 100);
+        let inner = solid(1, 16, 16, 200);
+        let frame = interpolate(&outer, &inner, 0.5, 1.0, 16, 16);
+
+        let pixel = |x: usize, y: usize| &frame[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4];
+        assert_eq!(pixel(8, 8), [150; 4]);
+        // The inner keyframe only covers the middle of the frame halfway to its octave.
+        for (x, y) in [(0, 0), (15, 0), (0, 15), (15, 15), (1, 8), (8, 1)] {
+            assert_eq!(pixel(x, y), [100; 4], "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn samples_up_to_the_last_texel() {
+        let keyframe = keyframe(0, 4, 2, 0);
+        let aspect = 2.0;
+        let texel = |x: usize, y: usize| -> [f32; 4] {
+            let i = (y * 4 + x) * 4;
+            std::array::from_fn(|c| keyframe.pixels[i + c] as f32)
+        };
+
+        assert_eq!(keyframe.sample(-aspect, -1.0), Some(texel(0, 0)));
+        // The last texel starts a pixel before the edge of the view.
+        assert_eq!(keyframe.sample(aspect * 0.5, 0.0), Some(texel(3, 1)));
+        assert_eq!(keyframe.sample(aspect * 0.5 + 1e-3, 0.0), None);
+        assert_eq!(keyframe.sample(aspect * 0.5, 1e-3), None);
+        assert_eq!(keyframe.sample(-aspect - 1e-3, -1.0), None);
+        assert_eq!(keyframe.sample(-aspect, -1.0 - 1e-3), None);
+        assert_eq!(keyframe.sample(aspect, 1.0), None);
+
+        // Halfway between texels is their average.
+        let (a, b) = (texel(1, 0), texel(2, 0));
+        let between = keyframe.sample(-aspect * 0.25, -1.0).unwrap();
+        assert_eq!(between, std::array::from_fn(|c| (a[c] + b[c]) / 2.0));
+    }
+}
@@ -1,21 +1,42 @@
 #![allow(clippy::too_many_arguments)]
 
-use crate::{encoder::Encoder, pipeline::Pipeline};
+use crate::{
+    config::{Config, Position},
+    encoder::FrameEncoder,
+    keyframe::{Keyframe, interpolate},
+    pipeline::Pipeline,
+};
 use indicatif::ProgressBar;
-use malachite::{Rational, base::rounding_modes::RoundingMode};
+use malachite::{
+    Natural, Rational,
+    base::{
+        num::conversion::traits::{FromStringBase, RoundingFrom},
+        rounding_modes::RoundingMode,
+    },
+};
 use malachite_float::Float;
+use std::ops::Range;
 
 mod compute;
 pub mod config;
 pub mod encoder;
+pub mod error;
+mod history;
+mod hud;
+mod keyframe;
 mod orbit;
 mod palette;
 pub mod pipeline;
+pub mod precision;
+mod reproject;
 mod ssaa;
+pub mod timeline;
 pub mod viewer;
 
 /// Render a png to `output` with the given `pipeline`.
 ///
+/// The png carries the config and exact position it was rendered at, see [`config::load`].
+///
 /// If `progress_bar` is supplied, the number of pixels processed will be displayed.
 /// You do NOT need to specify the length.
 pub fn render_png(
@@ -46,47 +67,214 @@ pub fn render_png(
     pipeline.log(frame)?;
     let pixels = pipeline.read_output_buffer_bytes();
     let (w, h) = pipeline.dimensions();
-    encoder::png(output, &pixels, w, h, true)
+    encoder::png_with_metadata(output, &pixels, w, h, true, &pipeline.png_metadata())
 }
 
-/// Render an mp4 to `output` with the given `pipeline`.
+/// Render a video with the given `pipeline`, streaming each frame into `encoder`.
+///
+/// `update` moves the pipeline to each frame in `frames` before it is rendered.
 ///
 /// If `progress_bar` is supplied, the number of processed frames will be displayed.
 /// You do NOT need to specify the length.
 pub fn render_mp4(
     pipeline: &mut Pipeline,
     progress_bar: Option<&ProgressBar>,
-    mut encoder: Encoder,
-    frames: usize,
-    mut zoom: impl FnMut(&mut Float),
+    mut encoder: impl FrameEncoder,
+    frames: Range<usize>,
+    mut update: impl FnMut(&mut Pipeline, usize),
+) -> std::io::Result<()> {
+    if let Some(bar) = progress_bar {
+        bar.set_length(frames.end as u64);
+        bar.set_position(frames.start as u64);
+    }
+
+    for i in frames {
+        update(pipeline, i);
+        while !pipeline.finished() {
+            pipeline.step_mandelbrot_headless();
+        }
+        pipeline.render_output();
+        let pixels = pipeline.read_output_buffer_bytes();
+        if let Some(bar) = progress_bar {
+            bar.inc(1);
+        }
+        encoder.set_metadata(pipeline.png_metadata());
+        encoder.render_frame(&pixels)?;
+        pipeline.log(i)?;
+    }
+
+    if let Some(bar) = progress_bar {
+        bar.finish();
+    }
+
+    encoder.finish()
+}
+
+/// Render a zoom video with the given `pipeline` by only computing keyframes at
+/// every power-of-two zoom level, then synthesizing the frames in between.
+///
+/// The `pipeline` renders the keyframes, so its dimensions must be `margin` times
+/// the `width` and `height` of the video. `octave` maps each frame index to its
+/// zoom depth in octaves relative to the pipeline's current position, where each
+/// octave halves the zoom. Keyframes are logged in the order they are rendered.
+///
+/// If `progress_bar` is supplied, the number of processed frames will be displayed.
+/// You do NOT need to specify the length.
+pub fn render_keyframes(
+    pipeline: &mut Pipeline,
+    progress_bar: Option<&ProgressBar>,
+    mut encoder: impl FrameEncoder,
+    frames: Range<usize>,
+    width: usize,
+    height: usize,
+    margin: f64,
+    octave: impl Fn(usize) -> f64,
+) -> std::io::Result<()> {
+    if let Some(bar) = progress_bar {
+        bar.set_length(frames.end as u64);
+        bar.set_position(frames.start as u64);
+    }
+
+    // Frames are cut from the keyframes, so their metadata describes the video.
+    let start = pipeline.position();
+    let config = pipeline.read_config(|config| Config {
+        width,
+        height,
+        ..config.clone()
+    });
+    let start_zoom = pipeline.read_position(|_, _, z| z.clone());
+    let base = &start_zoom * Float::from(margin);
+    let mut logged = 0;
+    let mut render_keyframe = |pipeline: &mut Pipeline, octave: i64| -> std::io::Result<Keyframe> {
+        pipeline.write_position(|_, _, z| {
+            *z = &base >> octave;
+        });
+        while !pipeline.finished() {
+            pipeline.step_mandelbrot_headless();
+        }
+        pipeline.render_output();
+        let (w, h) = pipeline.dimensions();
+        let keyframe = Keyframe {
+            octave,
+            pixels: pipeline.read_output_buffer_bytes(),
+            width: w,
+            height: h,
+        };
+        pipeline.log(logged)?;
+        logged += 1;
+        Ok(keyframe)
+    };
+
+    let mut keyframes: Option<(Keyframe, Keyframe)> = None;
+    for i in frames {
+        let t = octave(i);
+        let outer = t.floor() as i64;
+        if keyframes
+            .as_ref()
+            .is_none_or(|(current, _)| current.octave != outer)
+        {
+            // Zooming in reuses the inner keyframe as the next outer keyframe.
+            let next = match keyframes.take() {
+                Some((_, inner)) if inner.octave == outer => inner,
+                _ => render_keyframe(pipeline, outer)?,
+            };
+            keyframes = Some((next, render_keyframe(pipeline, outer + 1)?));
+        }
+
+        let (outer, inner) = keyframes.as_ref().unwrap();
+        let pixels = interpolate(outer, inner, t, margin, width, height);
+        if let Some(bar) = progress_bar {
+            bar.inc(1);
+        }
+        let whole = t.floor();
+        let zoom = (&start_zoom >> whole as i64) * Float::from((whole - t).exp2());
+        let position = Position {
+            zoom: zoom.to_string(),
+            ..start.clone()
+        };
+        encoder.set_metadata(pipeline::png_metadata(&config, &position));
+        encoder.render_frame(&pixels)?;
+    }
+
+    if let Some(bar) = progress_bar {
+        bar.finish();
+    }
+
+    encoder.finish()
+}
+
+/// Render an exponential map of the current position to `output` with the given `pipeline`.
+///
+/// Columns are angles around the centre and every row is a ring whose radius grows
+/// exponentially from the pipeline's zoom in the bottom row up to a radius of 2 in the
+/// top row, so the whole zoom is covered by a single tall image with square pixels.
+/// The map is rendered in tiles of the pipeline's height and streamed into `output`.
+///
+/// If `progress_bar` is supplied, the number of processed tiles will be displayed.
+/// You do NOT need to specify the length.
+pub fn render_exp_map(
+    pipeline: &mut Pipeline,
+    progress_bar: Option<&ProgressBar>,
     output: &str,
 ) -> std::io::Result<()> {
+    use std::{
+        f64::consts::{LN_2, TAU},
+        io::Write,
+    };
+
+    let (width, tile_rows) = pipeline.dimensions();
+    let zoom = pipeline.read_position(|_, _, z| z.clone());
+    let (zm, ze) = to_f32_exp(&zoom);
+    let depth = 2f64.ln() - ((zm as f64).ln() + ze as f64 * LN_2);
+    let rows = (depth * width as f64 / TAU).ceil().max(1.0) as usize;
+    let tiles = rows.div_ceil(tile_rows);
+
     if let Some(bar) = progress_bar {
-        bar.set_length(frames as u64);
+        bar.set_length(tiles as u64);
         bar.set_position(0);
     }
 
-    for i in 0..frames {
+    let file = std::fs::File::create(output)?;
+    let output = std::io::BufWriter::new(file);
+    let mut encoder = png::Encoder::new(output, width as u32, (tiles * tile_rows) as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, text) in pipeline.png_metadata() {
+        encoder.add_itxt_chunk(keyword, text)?;
+    }
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+
+    pipeline.set_mapping(pipeline::Mapping::Exponential);
+    for (i, tile) in (0..tiles).rev().enumerate() {
+        // The bottom row of the tile has a radius of `zoom * e^(tau * row / width)`.
+        let octaves = TAU * (tile * tile_rows) as f64 / (width as f64 * LN_2);
+        let whole = octaves.floor();
         pipeline.write_position(|_, _, z| {
-            zoom(z);
+            *z = (&zoom << whole as i64) * Float::from((octaves - whole).exp2());
         });
         while !pipeline.finished() {
             pipeline.step_mandelbrot_headless();
         }
         pipeline.render_output();
         let pixels = pipeline.read_output_buffer_bytes();
+        pipeline.log(i)?;
+
+        stream.write_all(&encoder::bgra_to_rgba(&pixels))?;
         if let Some(bar) = progress_bar {
             bar.inc(1);
         }
-        encoder.render_frame(&pixels)?;
-        pipeline.log(i)?;
     }
+    pipeline.set_mapping(pipeline::Mapping::Rectilinear);
+    pipeline.write_position(|_, _, z| *z = zoom);
 
     if let Some(bar) = progress_bar {
         bar.finish();
     }
 
-    encoder.finish(output)
+    stream.finish()?;
+    writer.finish()?;
+    Ok(())
 }
 
 /// Cast a slice to bytes.
@@ -105,26 +293,218 @@ pub fn to_f32_exp(x: &Float) -> (f32, i32) {
     }
 }
 
-pub fn float_from_str(str: &str) -> Float {
+/// Geometrically interpolate between the positive zooms `start` and `end`.
+///
+/// `t` of 0 and 1 return `start` and `end` exactly. Every other zoom is
+/// `start * (end / start)^t` computed directly from `t` at the precision of the zooms,
+/// so frames never accumulate rounding errors.
+pub fn interpolate_zoom(start: &Float, end: &Float, t: f64) -> Float {
+    if t <= 0.0 {
+        return start.clone();
+    }
+    if t >= 1.0 {
+        return end.clone();
+    }
+
+    let prec = start
+        .get_prec()
+        .max(end.get_prec())
+        .unwrap_or(precision::MIN_PRECISION)
+        .max(precision::MIN_PRECISION);
+    // The logarithm of the ratio grows with the octaves between the zooms, and the
+    // bits it takes up are lost again when it is exponentiated.
+    let octaves = (log2(end) - log2(start)).abs().max(1.0);
+    let work = prec + octaves.log2().ceil() as u64 + 32;
+
+    let mut ratio = end.clone();
+    ratio.set_prec(work);
+    ratio /= start;
+    let ln2 = ln2_prec(work);
+    let exponent = ln_prec(&ratio, &ln2, work) * Float::from(t);
+    let mut zoom = exp_prec(&exponent, &ln2, work) * start;
+    zoom.set_prec(prec);
+    zoom
+}
+
+/// Natural logarithm of the positive `x` with `prec` bits of precision, given `ln2`
+/// from [`ln2_prec`] at the same precision.
+fn ln_prec(x: &Float, ln2: &Float, prec: u64) -> Float {
+    // `x = m * 2^e` with `m` in `[0.5, 1)`, so `ln(x) = e * ln(2) + ln(m)`.
+    let e = x.get_exponent().unwrap_or(0);
+    let mut m = x.clone();
+    m.set_prec(prec);
+    m >>= e;
+
+    let ln_m = atanh_prec(&((&m - Float::from(1u32)) / (&m + Float::from(1u32))), prec) << 1;
+    ln_m + ln2 * Float::from(e)
+}
+
+/// Exponential of `x` with `prec` bits of precision, given `ln2` from [`ln2_prec`] at
+/// the same precision.
+fn exp_prec(x: &Float, ln2: &Float, prec: u64) -> Float {
+    // `exp(x) = 2^k * exp(r)` with `|r| <= ln(2) / 2`, where the series converges fast.
+    let k = (f64::rounding_from(x, RoundingMode::Nearest).0 / std::f64::consts::LN_2).round();
+    let mut r = x.clone();
+    r.set_prec(prec);
+    r -= ln2 * Float::from(k);
+
+    let mut sum = Float::from(1u32);
+    sum.set_prec(prec);
+    let mut term = sum.clone();
+    for n in 1u32.. {
+        term = term * &r / Float::from(n);
+        if is_negligible(&term, &sum, prec) {
+            break;
+        }
+        sum += &term;
+    }
+    sum << k as i64
+}
+
+/// `ln(2) = 2 * atanh(1 / 3)` with `prec` bits of precision.
+fn ln2_prec(prec: u64) -> Float {
+    let mut third = Float::from(1u32);
+    third.set_prec(prec);
+    third /= Float::from(3u32);
+    atanh_prec(&third, prec) << 1
+}
+
+/// Inverse hyperbolic tangent of `|x| < 1` with `prec` bits of precision, converging
+/// quickly for small `x`.
+fn atanh_prec(x: &Float, prec: u64) -> Float {
+    let mut power = x.clone();
+    power.set_prec(prec);
+    let square = &power * &power;
+    let mut sum = power.clone();
+    for n in (3u32..).step_by(2) {
+        power *= &square;
+        let term = &power / Float::from(n);
+        if is_negligible(&term, &sum, prec) {
+            break;
+        }
+        sum += term;
+    }
+    sum
+}
+
+/// Whether adding `term` to `sum` no longer changes its `prec` bits.
+fn is_negligible(term: &Float, sum: &Float, prec: u64) -> bool {
+    match (term.get_exponent(), sum.get_exponent()) {
+        (Some(term), Some(sum)) => (term as i64) < sum as i64 - prec as i64 - 1,
+        // The term is zero.
+        (None, _) => true,
+        (Some(_), None) => false,
+    }
+}
+
+/// Base 2 logarithm of `x`, which is 0 for zero.
+pub fn log2(x: &Float) -> f64 {
+    x.sci_mantissa_and_exponent_round::<f64>(RoundingMode::Nearest)
+        .map_or(0.0, |(m, e, _)| e as f64 + m.log2())
+}
+
+/// Raise `base` to the power of `exp` with `prec` bits of precision.
+pub fn pow_prec(base: &Float, mut exp: u64, prec: u64) -> Float {
+    let mut base = base.clone();
+    base.set_prec(prec);
+    let mut result = Float::from(1u32);
+    result.set_prec(prec);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result *= &base;
+        }
+        base = &base * &base;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Parse a number at the precision it was written with, returning `None` if `str`
+/// is not a number.
+///
+/// Accepts decimals (`-1.25e-3`), hex floats (`0x1.4p-3`) and powers of two, either
+/// on their own (`2^-1400`) or scaling another number (`1.5*2^-1400`). Every form is
+/// converted exactly, except for decimals that have no finite binary expansion.
+///
+/// Numbers that would take more than [`precision::MAX_PRECISION`] bits, or that are
+/// scaled by a power of two beyond it, are rejected.
+pub fn float_from_str(str: &str) -> Option<Float> {
+    let str = str.trim();
+    if let Some(abs) = str.strip_prefix('-') {
+        if abs.starts_with(['-', '+']) {
+            return None;
+        }
+        return float_from_str(abs).map(|abs| -abs);
+    }
+
+    if let Some((base, exp)) = str.split_once('^') {
+        let (scale, base) = match base.rsplit_once('*') {
+            Some((scale, base)) => (float_from_str(scale)?, base),
+            None => (Float::from(1u32), base),
+        };
+        if base.trim() != "2" {
+            return None;
+        }
+        return Some(scale << binary_exponent(exp.trim())?);
+    }
+
+    if let Some(hex) = str.strip_prefix("0x").or_else(|| str.strip_prefix("0X")) {
+        return hex_float_from_str(hex);
+    }
+
     let mut digits = str
         .chars()
         .take_while(|c| *c != 'e')
         .filter(|c| c.is_ascii_digit())
-        .count() as u32;
+        .count() as u64;
 
     if let Some(index) = str.find("e") {
-        digits += str::parse::<i32>(&str[index + 1..]).unwrap().unsigned_abs();
+        digits += str::parse::<i64>(&str[index + 1..]).ok()?.unsigned_abs();
     }
 
-    let prec = (digits as f64 * 3.322).ceil() as u64 + 16;
-    let prec = prec.max(53);
+    // Checked before parsing, since the exact rational of a huge exponent is huge too.
+    let prec = precision::decimal(digits);
+    if prec > precision::MAX_PRECISION {
+        return None;
+    }
 
-    Float::from_rational_prec_round(
-        Rational::from_sci_string_simplest(str).unwrap(),
-        prec,
-        RoundingMode::Nearest,
+    Some(
+        Float::from_rational_prec_round(
+            Rational::from_sci_string_simplest(str)?,
+            prec,
+            RoundingMode::Nearest,
+        )
+        .0,
     )
-    .0
+}
+
+/// Parse a power of two exponent, rejecting those beyond [`precision::MAX_PRECISION`].
+fn binary_exponent(exp: &str) -> Option<i64> {
+    exp.parse::<i64>()
+        .ok()
+        .filter(|exp| exp.unsigned_abs() <= precision::MAX_PRECISION)
+}
+
+/// Parse the digits of a hex float after the `0x`, such as `1.4p-3`.
+fn hex_float_from_str(hex: &str) -> Option<Float> {
+    let (digits, exp) = match hex.split_once(['p', 'P']) {
+        Some((digits, exp)) => (digits, binary_exponent(exp)?),
+        None => (hex, 0),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if int.is_empty() && frac.is_empty() {
+        return None;
+    }
+
+    // Every hex digit is four bits, so the mantissa is represented exactly.
+    let prec = precision::hex(int.len() + frac.len());
+    if prec > precision::MAX_PRECISION {
+        return None;
+    }
+    let mantissa = Natural::from_string_base(16, &format!("{int}{frac}"))?;
+    let mantissa =
+        Float::from_rational_prec_round(Rational::from(mantissa), prec, RoundingMode::Nearest).0;
+    Some(mantissa << (exp - 4 * frac.len() as i64))
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -143,3 +523,162 @@ pub fn start_viewer() {
     let memory = viewer::Memory::from_config(config);
     viewer::run(memory);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `num / den` with `prec` bits of precision.
+    fn ratio(num: u32, den: u32, prec: u64) -> Float {
+        let mut x = Float::from(num);
+        x.set_prec(prec);
+        x /= Float::from(den);
+        x
+    }
+
+    /// Assert that `actual` is within a relative `2^-bits` of `expected`.
+    fn assert_close(actual: &Float, expected: &Float, bits: u64) {
+        let error = actual - expected;
+        if let (Some(error), Some(expected)) = (error.get_exponent(), expected.get_exponent()) {
+            assert!(
+                (error as i64) < expected as i64 - bits as i64,
+                "{actual} is not within 2^-{bits} of {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn interpolate_zoom_ends() {
+        let start = ratio(3, 1, 256);
+        let end = ratio(5, 7, 256) >> 500;
+
+        for (t, expected) in [(0.0, &start), (-1.0, &start), (1.0, &end), (2.0, &end)] {
+            let zoom = interpolate_zoom(&start, &end, t);
+            assert_eq!(zoom, *expected);
+            assert_eq!(zoom.get_prec(), expected.get_prec());
+        }
+    }
+
+    #[test]
+    fn interpolate_zoom_geometric_mean() {
+        for (start, end) in [
+            (ratio(3, 1, 256), ratio(5, 7, 256) >> 500),
+            (ratio(1, 3, 1024), ratio(2, 1, 1024) >> 3000),
+            (ratio(2, 1, 128), ratio(9, 5, 128)),
+        ] {
+            let prec = start.get_prec().unwrap();
+            let zoom = interpolate_zoom(&start, &end, 0.5);
+            assert_eq!(zoom.get_prec(), Some(prec));
+            assert_close(&(&zoom * &zoom), &(&start * &end), prec - 4);
+        }
+    }
+
+    #[test]
+    fn ln_inverts_exp() {
+        for prec in [64, 256, 2048] {
+            let ln2 = ln2_prec(prec);
+            for x in [
+                ratio(3, 4, prec),
+                -ratio(3, 4, prec),
+                ratio(1, 10, prec),
+                ratio(186, 5, prec),
+                -ratio(2000, 3, prec),
+            ] {
+                let y = ln_prec(&exp_prec(&x, &ln2, prec), &ln2, prec);
+                assert_close(&y, &x, prec - 8);
+            }
+        }
+    }
+
+    #[test]
+    fn float_from_str_forms() {
+        let pow2 = |exp: i64| Float::from(1u32) << exp;
+        for (str, expected) in [
+            ("0x1.8p-3", Float::from(0.1875)),
+            ("0X1P4", Float::from(16u32)),
+            ("-0x1p0", Float::from(-1.0)),
+            ("0x.8", Float::from(0.5)),
+            ("2^-100", pow2(-100)),
+            (" 2^ -3 ", Float::from(0.125)),
+            ("-2^3", Float::from(-8.0)),
+            ("1.5*2^-1400", Float::from(1.5) >> 1400),
+            ("0x3*2^-2", Float::from(0.75)),
+            ("-1.5", Float::from(-1.5)),
+            ("0.25e1", Float::from(2.5)),
+        ] {
+            assert_eq!(float_from_str(str), Some(expected), "{str}");
+        }
+
+        let decimal = float_from_str("-1.25e-3").unwrap();
+        assert_eq!(
+            f64::rounding_from(&decimal, RoundingMode::Nearest).0,
+            -1.25e-3
+        );
+        // Decimals keep a bit more than the precision of the digits they were written with.
+        let digits = "0.".to_string() + &"3".repeat(100);
+        assert!(float_from_str(&digits).unwrap().get_prec().unwrap() >= 333);
+    }
+
+    #[test]
+    fn float_from_str_rejects_malformed() {
+        for str in [
+            "",
+            " ",
+            "-",
+            "--1",
+            "-+1",
+            "abc",
+            "1.2.3",
+            "1e",
+            "1e+-2",
+            "one",
+            "1e99999999999",
+            "2^",
+            "2^x",
+            "2^1.5",
+            "3^4",
+            "^3",
+            "*2^3",
+            "1.5*3^2",
+            "x*2^3",
+            "2^99999999999999999999",
+            "0x",
+            "0x.",
+            "0xg",
+            "0x1p",
+            "0x1.8pz",
+            "0x1p1.5",
+            "0x-1",
+            "0x1.-8",
+        ] {
+            assert_eq!(float_from_str(str), None, "{str:?}");
+        }
+    }
+
+    #[test]
+    fn float_from_str_rejects_oversized() {
+        let limit = precision::MAX_PRECISION as i64;
+        assert_eq!(
+            float_from_str(&format!("2^-{limit}")),
+            Some(Float::from(1u32) >> limit)
+        );
+        assert_eq!(
+            float_from_str(&format!("0x1p{limit}")),
+            Some(Float::from(1u32) << limit)
+        );
+        assert!(float_from_str("1e-3000").is_some());
+
+        for str in [
+            "1e999999999".to_string(),
+            "-1e-999999999".to_string(),
+            "1e9223372036854775807".to_string(),
+            format!("2^-{}", limit + 1),
+            format!("1.5*2^{}", limit + 1),
+            format!("0x1p-{}", limit + 1),
+            format!("0x{}", "f".repeat(limit as usize / 4 + 1)),
+            format!("0.{}", "3".repeat(limit as usize / 3)),
+        ] {
+            assert_eq!(float_from_str(&str), None, "{}", &str[..str.len().min(32)]);
+        }
+    }
+}
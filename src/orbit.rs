@@ -28,6 +28,16 @@ impl WFloat {
     const ZERO: Self = Self { m: 0.0, e: 0 };
 }
 
+/// Largest storage buffer binding that every WebGPU device supports.
+const MIN_STORAGE_BUFFER_BINDING_SIZE: usize = 128 << 20;
+
+/// Number of reference orbit points the point buffer holds, which bounds the iterations
+/// of a render as the orbit has a point per iteration.
+pub const MAX_ITERATIONS: usize = 1_000_000;
+
+const _: () =
+    assert!(MAX_ITERATIONS * std::mem::size_of::<RefPoint>() <= MIN_STORAGE_BUFFER_BINDING_SIZE);
+
 /// Reference orbit points and series approximation coefficients.
 pub struct Orbit {
     pub bind_group: wgpu::BindGroup,
@@ -40,8 +50,6 @@ pub struct Orbit {
 
 impl Orbit {
     pub fn new(device: &wgpu::Device) -> Self {
-        // TODO: This is not true!
-        const MAX_ITERATIONS: usize = 1_000_000;
         let size = std::mem::size_of::<RefPoint>() * MAX_ITERATIONS;
 
         let point_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -76,7 +84,7 @@ impl Orbit {
             bind_group,
             point_buffer,
             uniform,
-            points: Vec::with_capacity(size),
+            points: Vec::with_capacity(MAX_ITERATIONS),
             coefficients: [WFloat::ZERO; 6],
             polylim: 0,
         }
@@ -111,6 +119,8 @@ impl Orbit {
     }
 
     /// Must be called before [`Orbit::write_buffers`].
+    ///
+    /// The orbit is computed with `prec` bits, see [`crate::precision::orbit`].
     // Algorithm ported from JS: https://github.com/HastingsGreer/mandeljs/blob/7bb12c6ee2214e4eea82a30498de85823b3be474/main.js#L410
     pub fn compute_reference_orbit(
         &mut self,
@@ -118,11 +128,11 @@ impl Orbit {
         y0: &Float,
         z: &Float,
         iterations: usize,
+        prec: u64,
     ) {
         self.points.clear();
         self.polylim = 0;
 
-        let prec = z.get_prec().unwrap_or(53) + 10;
         let mut x = Float::ZERO;
         x.set_prec(prec);
         let mut y = x.clone();
@@ -138,7 +148,7 @@ impl Orbit {
         let mut dy = WFloat::ZERO;
 
         let mut not_failed = true;
-        for i in 0..iterations {
+        for i in 0..iterations.min(MAX_ITERATIONS) {
             let (xm, xe) = to_f32_exp(&x);
             let (ym, ye) = to_f32_exp(&y);
 
@@ -249,14 +259,33 @@ impl Orbit {
     /// Uploads point and approximation uniform buffers.
     ///
     /// Call [`Orbit::compute_reference_orbit`] first.
-    pub fn write_buffers(&self, queue: &wgpu::Queue, z: &Float) {
+    ///
+    /// The coefficients are rotated by `rotation` degrees so that the series can be
+    /// evaluated with the unrotated screen delta.
+    pub fn write_buffers(&self, queue: &wgpu::Queue, z: &Float, rotation: f32) {
         let (r, rexp) = to_f32_exp(z);
         let r = WFloat { m: r, e: rexp };
 
-        let poly_scape_exp = mul(
-            WFloat { m: 1.0, e: 0 },
-            maxabs(self.coefficients[0], self.coefficients[1]),
-        );
+        // The nth order term scales with the nth power of the delta.
+        let turn = |n: f64| {
+            let angle = n * (rotation as f64).to_radians();
+            (
+                WFloat {
+                    m: angle.cos() as f32,
+                    e: 0,
+                },
+                WFloat {
+                    m: angle.sin() as f32,
+                    e: 0,
+                },
+            )
+        };
+        let [bx, by, cx, cy, dx, dy] = self.coefficients;
+        let (bx, by) = cmul((bx, by), turn(1.0));
+        let (cx, cy) = cmul((cx, cy), turn(2.0));
+        let (dx, dy) = cmul((dx, dy), turn(3.0));
+
+        let poly_scape_exp = mul(WFloat { m: 1.0, e: 0 }, maxabs(bx, by));
 
         let poly_scale = WFloat {
             m: 1.0,
@@ -264,12 +293,12 @@ impl Orbit {
         };
 
         let poly_scaled = [
-            mul(poly_scale, self.coefficients[0]),
-            mul(poly_scale, self.coefficients[1]),
-            mul(poly_scale, mul(r, self.coefficients[2])),
-            mul(poly_scale, mul(r, self.coefficients[3])),
-            mul(poly_scale, mul(r, mul(r, self.coefficients[4]))),
-            mul(poly_scale, mul(r, mul(r, self.coefficients[5]))),
+            mul(poly_scale, bx),
+            mul(poly_scale, by),
+            mul(poly_scale, mul(r, cx)),
+            mul(poly_scale, mul(r, cy)),
+            mul(poly_scale, mul(r, mul(r, dx))),
+            mul(poly_scale, mul(r, mul(r, dy))),
         ]
         .map(|d| d.m * 2f32.powi(d.e));
 
@@ -283,6 +312,11 @@ impl Orbit {
         queue.write_buffer(&self.uniform, 0, byte_slice(&[uniform]));
         queue.write_buffer(&self.point_buffer, 0, byte_slice(&self.points));
     }
+
+    /// Reference point `k` as `(x, y)`, scaled down by `2^e`, and `e`.
+    pub fn point(&self, k: usize) -> Option<(f32, f32, i32)> {
+        self.points.get(k).map(|point| (point.x, point.y, point.s))
+    }
 }
 
 fn split(a: WFloat, b: WFloat) -> (f32, f32, i32) {
@@ -324,6 +358,10 @@ fn mul(a: WFloat, b: WFloat) -> WFloat {
     WFloat { m, e }
 }
 
+fn cmul((ax, ay): (WFloat, WFloat), (bx, by): (WFloat, WFloat)) -> (WFloat, WFloat) {
+    (sub(mul(ax, bx), mul(ay, by)), add(mul(ax, by), mul(ay, bx)))
+}
+
 fn maxabs(a: WFloat, b: WFloat) -> WFloat {
     let (am, bm, ret_e) = split(a, b);
     WFloat {
@@ -97,63 +97,78 @@ impl Palette {
     }
 }
 
-/// Matches `palette` on [`colorgrad`] preset functions.
-pub fn parse_palette(palette: &str) -> Vec<Sbgr> {
-    macro_rules! match_colorgrad {
-        (palette, $($arm:ident,)*) => {
-            match palette {
-                $(stringify!($arm) => generate_gradient(&colorgrad::preset::$arm()),)*
-                _ => {
-                    println!("Unknown palette: {}", palette);
-                    std::process::exit(1);
+macro_rules! presets {
+    ($($variant:ident => $preset:ident,)*) => {
+        /// [`colorgrad`] preset gradients.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Preset {
+            $($variant,)*
+        }
+
+        impl Preset {
+            /// Every preset, in alphabetical order.
+            pub const ALL: &[Preset] = &[$(Self::$variant,)*];
+        }
+
+        impl std::fmt::Display for Preset {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Self::$variant => f.write_str(stringify!($preset)),)*
                 }
             }
-        };
-    }
+        }
 
-    match_colorgrad!(
-        palette,
-        blues,
-        br_bg,
-        bu_gn,
-        bu_pu,
-        cividis,
-        cool,
-        cubehelix_default,
-        gn_bu,
-        greens,
-        greys,
-        inferno,
-        magma,
-        or_rd,
-        oranges,
-        pi_yg,
-        plasma,
-        pr_gn,
-        pu_bu,
-        pu_bu_gn,
-        pu_or,
-        pu_rd,
-        purples,
-        rainbow,
-        rd_bu,
-        rd_gy,
-        rd_pu,
-        rd_yl_bu,
-        rd_yl_gn,
-        reds,
-        sinebow,
-        spectral,
-        turbo,
-        viridis,
-        warm,
-        yl_gn,
-        yl_gn_bu,
-        yl_or_br,
-        yl_or_rd,
-    )
+        /// Samples the [`colorgrad`] preset of `palette`.
+        pub fn parse_palette(palette: Preset) -> Vec<Sbgr> {
+            match palette {
+                $(Preset::$variant => generate_gradient(&colorgrad::preset::$preset()),)*
+            }
+        }
+    };
 }
 
+presets!(
+    Blues => blues,
+    BrBg => br_bg,
+    BuGn => bu_gn,
+    BuPu => bu_pu,
+    Cividis => cividis,
+    Cool => cool,
+    CubehelixDefault => cubehelix_default,
+    GnBu => gn_bu,
+    Greens => greens,
+    Greys => greys,
+    Inferno => inferno,
+    Magma => magma,
+    OrRd => or_rd,
+    Oranges => oranges,
+    PiYg => pi_yg,
+    Plasma => plasma,
+    PrGn => pr_gn,
+    PuBu => pu_bu,
+    PuBuGn => pu_bu_gn,
+    PuOr => pu_or,
+    PuRd => pu_rd,
+    Purples => purples,
+    Rainbow => rainbow,
+    RdBu => rd_bu,
+    RdGy => rd_gy,
+    RdPu => rd_pu,
+    RdYlBu => rd_yl_bu,
+    RdYlGn => rd_yl_gn,
+    Reds => reds,
+    Sinebow => sinebow,
+    Spectral => spectral,
+    Turbo => turbo,
+    Viridis => viridis,
+    Warm => warm,
+    YlGn => yl_gn,
+    YlGnBu => yl_gn_bu,
+    YlOrBr => yl_or_br,
+    YlOrRd => yl_or_rd,
+);
+
 fn generate_gradient(grad: &impl colorgrad::Gradient) -> Vec<Sbgr> {
     let mut palette = Vec::new();
     let samples = 16;
@@ -1,15 +1,21 @@
+pub use crate::compute::{Mapping, PixelState};
 use crate::{
     compute::ComputePipeline,
-    config::Config,
+    config::{Config, PNG_CONFIG_KEYWORD, PNG_POSITION_KEYWORD, Position},
     float_from_str,
+    hud::Hud,
     orbit::Orbit,
     palette::{Palette, parse_palette},
+    precision,
+    reproject::Reprojection,
     ssaa::SsaaPipeline,
 };
 use glazer::winit::window::Window;
 use malachite_float::Float;
+use std::time::Duration;
 #[cfg(target_arch = "wasm32")]
 use std::{cell::RefCell, rc::Rc};
+use web_time::Instant;
 
 #[cfg(target_arch = "wasm32")]
 pub struct PipelineBuilder {
@@ -78,6 +84,7 @@ impl PipelineBuilder {
 
 pub struct Pipeline {
     surface: Option<wgpu::Surface<'static>>,
+    surface_config: Option<wgpu::SurfaceConfiguration>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: Config,
@@ -88,12 +95,23 @@ pub struct Pipeline {
     ssaa: SsaaPipeline,
     orbit: Orbit,
     palette: Palette,
+    mapping: Mapping,
+    /// Overlay drawn over the surface, if there is one.
+    hud: Option<Hud>,
+    /// Previous frame shown while rendering to a surface.
+    reprojection: Option<Reprojection>,
     //
     finished_render: bool,
     updated_position: bool,
     x: Float,
     y: Float,
     z: Float,
+    /// Bits of precision needed by the current view.
+    precision: u64,
+    /// Pixels left to render after the last step that counted them.
+    remaining: u32,
+    /// Time taken by the last reference orbit.
+    orbit_time: Duration,
     //
     log: Option<Box<dyn std::io::Write>>,
 }
@@ -104,7 +122,8 @@ impl Pipeline {
         config: Config,
         log: Option<Box<dyn std::io::Write>>,
     ) -> Self {
-        env_logger::init();
+        // Several pipelines share the logger, such as the viewer and its snapshots.
+        _ = env_logger::try_init();
 
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
         let surface = window.map(|window| {
@@ -161,6 +180,7 @@ impl Pipeline {
         config: Config,
         log: Option<Box<dyn std::io::Write>>,
     ) -> Self {
+        let mut surface_config = None;
         let surface_format = if let Some(surface) = surface.as_mut() {
             let surface_caps = surface.get_capabilities(&adapter);
             let surface_format = surface_caps
@@ -182,12 +202,13 @@ impl Pipeline {
                 desired_maximum_frame_latency: 2,
             };
             surface.configure(&device, &config);
+            surface_config = Some(config);
             surface_format
         } else {
             wgpu::TextureFormat::Rgba8UnormSrgb
         };
 
-        let ssaa = SsaaPipeline::new(
+        let mut ssaa = SsaaPipeline::new(
             &device,
             surface_format,
             surface.is_some(),
@@ -197,23 +218,23 @@ impl Pipeline {
         );
         let compute = ComputePipeline::new(&device, surface_format, &ssaa, &config);
         let orbit = Orbit::new(&device);
-        let palette = Palette::new(&device, &queue, &parse_palette(&config.palette));
+        let palette = Palette::new(&device, &queue, &parse_palette(config.palette));
+        let hud = surface
+            .is_some()
+            .then(|| Hud::new(&device, &queue, surface_format));
+        let reprojection = surface
+            .is_some()
+            .then(|| Reprojection::new(&device, &mut ssaa, config.width, config.height));
 
-        let (bytes_per_row, buffer_size) =
-            output_buffer_bytes_per_row_and_size(config.width, config.height);
-        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: buffer_size as u64,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
+        let (output_buffer, bytes_per_row) = output_buffer(&device, config.width, config.height);
 
-        let z = float_from_str(&config.zoom);
-        let x = float_from_str(&config.x);
-        let y = float_from_str(&config.y);
+        let z = float_from_str(&config.zoom).expect("config has a valid zoom");
+        let x = float_from_str(&config.x).expect("config has a valid x");
+        let y = float_from_str(&config.y).expect("config has a valid y");
 
-        Pipeline {
+        let mut pipeline = Pipeline {
             surface,
+            surface_config,
             device,
             queue,
             config,
@@ -224,27 +245,80 @@ impl Pipeline {
             ssaa,
             orbit,
             palette,
+            mapping: Mapping::default(),
+            hud,
+            reprojection,
             //
             finished_render: false,
             updated_position: true,
             x,
             y,
             z,
+            precision: precision::MIN_PRECISION,
+            remaining: 0,
+            orbit_time: Duration::ZERO,
             //
             log,
-        }
+        };
+        pipeline.update_precision();
+        pipeline
     }
 
     pub fn dimensions(&self) -> (usize, usize) {
         (self.config.width, self.config.height)
     }
 
+    /// Reallocate every buffer for a `width` by `height` output, restarting the render.
+    ///
+    /// The position is unchanged, so the view keeps its centre and half height.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width == 0 || height == 0 || (width, height) == self.dimensions() {
+            return;
+        }
+
+        self.config.width = width;
+        self.config.height = height;
+        self.reallocate();
+        self.updated_position = true;
+        self.update_precision();
+    }
+
+    /// Reallocate every buffer for the size and supersampling of the config.
+    fn reallocate(&mut self) {
+        let (width, height) = self.dimensions();
+        if let (Some(surface), Some(surface_config)) = (&self.surface, &mut self.surface_config) {
+            surface_config.width = width as u32;
+            surface_config.height = height as u32;
+            surface.configure(&self.device, surface_config);
+        }
+        self.ssaa
+            .resize(&self.device, width, height, self.config.ssaa);
+        self.compute.resize(&self.device, &self.ssaa, width, height);
+        if let Some(reprojection) = &mut self.reprojection {
+            reprojection.resize(&self.device, &mut self.ssaa, width, height);
+        }
+        (self.output_buffer, self.bytes_per_row) = output_buffer(&self.device, width, height);
+        self.finished_render = false;
+    }
+
     pub fn total_pixels(&self) -> usize {
         let sf = self.ssaa.ssaa_factor();
         let (w, h) = self.dimensions();
         w * sf * h * sf
     }
 
+    /// Change how pixels are mapped to the complex plane, restarting the render.
+    pub fn set_mapping(&mut self, mapping: Mapping) {
+        self.mapping = mapping;
+        self.updated_position = true;
+    }
+
+    /// Rotate the view to `degrees` without resetting the position, restarting the render.
+    pub fn set_rotation(&mut self, degrees: f32) {
+        self.config.rotation = degrees;
+        self.updated_position = true;
+    }
+
     pub fn read_position<R>(&mut self, f: impl FnOnce(&Float, &Float, &Float) -> R) -> R {
         f(&self.x, &self.y, &self.z)
     }
@@ -255,40 +329,63 @@ impl Pipeline {
     ) -> R {
         self.updated_position = true;
         let result = f(&mut self.x, &mut self.y, &mut self.z);
+        self.update_precision();
+        result
+    }
 
-        let xexp = self.x.get_exponent().unwrap_or(0).unsigned_abs();
-        let yexp = self.y.get_exponent().unwrap_or(0).unsigned_abs();
-        let zexp = self.z.get_exponent().unwrap_or(0).unsigned_abs();
-        let required = (64 + zexp + xexp.max(yexp)) as u64;
-
-        if self.x.get_prec().is_some_and(|p| p < required) {
-            self.x.set_prec(required);
-        }
-        if self.y.get_prec().is_some_and(|p| p < required) {
-            self.y.set_prec(required);
-        }
-        if self.z.get_prec().is_some_and(|p| p < required) {
-            self.z.set_prec(required);
-        }
+    /// Bits of precision of the position, see [`precision::position`].
+    pub fn precision(&self) -> u64 {
+        self.precision
+    }
 
-        result
+    /// Recompute the precision needed by the current view and widen the position to it.
+    fn update_precision(&mut self) {
+        let sf = self.ssaa.ssaa_factor();
+        let (w, h) = self.dimensions();
+        self.precision = precision::position(
+            &self.x,
+            &self.y,
+            &self.z,
+            w * sf,
+            h * sf,
+            self.config.precision,
+        );
+        precision::widen(&mut self.x, self.precision);
+        precision::widen(&mut self.y, self.precision);
+        precision::widen(&mut self.z, self.precision);
     }
 
     pub fn read_config<R>(&mut self, f: impl FnOnce(&Config) -> R) -> R {
         f(&self.config)
     }
 
+    /// Change the config, restarting the render.
+    ///
+    /// The position is only reparsed when `x`, `y` or `zoom` change, so changing other
+    /// fields keeps the position navigated to.
     pub fn write_config<R>(&mut self, f: impl FnOnce(&mut Config) -> R) -> R {
+        let old = self.config.clone();
         let result = f(&mut self.config);
         self.updated_position = true;
-        self.z = float_from_str(&self.config.zoom);
-        self.x = float_from_str(&self.config.x);
-        self.y = float_from_str(&self.config.y);
-        self.palette = Palette::new(
-            &self.device,
-            &self.queue,
-            &parse_palette(&self.config.palette),
-        );
+
+        if (&self.config.x, &self.config.y, &self.config.zoom) != (&old.x, &old.y, &old.zoom) {
+            self.z = float_from_str(&self.config.zoom).expect("config has a valid zoom");
+            self.x = float_from_str(&self.config.x).expect("config has a valid x");
+            self.y = float_from_str(&self.config.y).expect("config has a valid y");
+        }
+        if self.config.palette != old.palette {
+            self.palette = Palette::new(
+                &self.device,
+                &self.queue,
+                &parse_palette(self.config.palette),
+            );
+        }
+        if (self.config.width, self.config.height, self.config.ssaa)
+            != (old.width, old.height, old.ssaa)
+        {
+            self.reallocate();
+        }
+        self.update_precision();
         result
     }
 
@@ -306,10 +403,11 @@ impl Pipeline {
                 let mut encoder = self
                     .device
                     .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-                self.ssaa.render_pass(
-                    &mut encoder,
-                    Some(surface.texture.create_view(&Default::default())),
-                );
+                // The pixels that finished last may still be fading in.
+                if let Some(reprojection) = &mut self.reprojection {
+                    reprojection.render_pass(&mut encoder);
+                }
+                self.render_surface(&mut encoder, &surface);
                 self.queue.submit([encoder.finish()]);
                 surface.present();
             }
@@ -317,14 +415,7 @@ impl Pipeline {
             return 0;
         }
 
-        if self.updated_position {
-            self.updated_position = false;
-            self.orbit
-                .compute_reference_orbit(&self.x, &self.y, &self.z, iterations);
-            self.orbit.write_buffers(&self.queue, &self.z);
-            self.compute
-                .write_buffers(&self.queue, &self.config, &self.z, &self.palette);
-        }
+        self.upload_position(iterations);
 
         let mut encoder = self
             .device
@@ -338,15 +429,17 @@ impl Pipeline {
             self.config.width,
             self.config.height,
         );
-        self.ssaa.render_pass(
-            &mut encoder,
-            surface_texture
-                .as_ref()
-                .map(|surface_texture| surface_texture.texture.create_view(&Default::default())),
-        );
+        if let Some(reprojection) = &mut self.reprojection {
+            reprojection.render_pass(&mut encoder);
+        }
+        match &surface_texture {
+            Some(surface_texture) => self.render_surface(&mut encoder, surface_texture),
+            None => self.ssaa.render_pass(&mut encoder, None),
+        }
         let remaining = self
             .compute
             .remaining_pixels(&self.device, &self.queue, encoder);
+        self.remaining = remaining;
         self.finished_render = remaining == 0;
         if let Some(surface) = surface_texture {
             surface.present();
@@ -358,14 +451,7 @@ impl Pipeline {
     ///
     /// Continues to draw whether or not pixels are remaining.
     pub fn force_step_mandelbrot(&mut self, iterations: usize) {
-        if self.updated_position {
-            self.updated_position = false;
-            self.orbit
-                .compute_reference_orbit(&self.x, &self.y, &self.z, iterations);
-            self.orbit.write_buffers(&self.queue, &self.z);
-            self.compute
-                .write_buffers(&self.queue, &self.config, &self.z, &self.palette);
-        }
+        self.upload_position(iterations);
 
         let surface_texture = self
             .surface
@@ -384,13 +470,21 @@ impl Pipeline {
             self.config.width,
             self.config.height,
         );
-        self.ssaa.render_pass(
-            &mut encoder,
-            surface_texture
-                .as_ref()
-                .map(|surface_texture| surface_texture.texture.create_view(&Default::default())),
-        );
-        self.queue.submit([encoder.finish()]);
+        if let Some(reprojection) = &mut self.reprojection {
+            reprojection.render_pass(&mut encoder);
+        }
+        match &surface_texture {
+            Some(surface_texture) => self.render_surface(&mut encoder, surface_texture),
+            None => self.ssaa.render_pass(&mut encoder, None),
+        }
+        // Counting the remaining pixels waits for the frame, so only do it when shown.
+        if self.hud_visible() {
+            self.remaining = self
+                .compute
+                .remaining_pixels(&self.device, &self.queue, encoder);
+        } else {
+            self.queue.submit([encoder.finish()]);
+        }
         if let Some(surface) = surface_texture {
             surface.present();
         }
@@ -402,14 +496,7 @@ impl Pipeline {
             return 0;
         }
 
-        if self.updated_position {
-            self.updated_position = false;
-            self.orbit
-                .compute_reference_orbit(&self.x, &self.y, &self.z, self.config.iterations);
-            self.orbit.write_buffers(&self.queue, &self.z);
-            self.compute
-                .write_buffers(&self.queue, &self.config, &self.z, &self.palette);
-        }
+        self.upload_position(self.config.iterations);
 
         let mut encoder = self
             .device
@@ -426,10 +513,136 @@ impl Pipeline {
         let remaining = self
             .compute
             .remaining_pixels(&self.device, &self.queue, encoder);
+        self.remaining = remaining;
         self.finished_render = remaining == 0;
         remaining
     }
 
+    /// Recompute the reference orbit and upload the position, if it changed.
+    fn upload_position(&mut self, iterations: usize) {
+        if !self.updated_position {
+            return;
+        }
+
+        self.updated_position = false;
+        let start = Instant::now();
+        self.orbit.compute_reference_orbit(
+            &self.x,
+            &self.y,
+            &self.z,
+            iterations,
+            precision::orbit(self.precision),
+        );
+        self.orbit_time = start.elapsed();
+        self.orbit
+            .write_buffers(&self.queue, &self.z, self.config.rotation);
+        self.compute.write_buffers(
+            &self.queue,
+            &self.config,
+            &self.z,
+            &self.palette,
+            self.mapping,
+        );
+        if let Some(reprojection) = &mut self.reprojection {
+            let position = (
+                self.x.clone(),
+                self.y.clone(),
+                self.z.clone(),
+                self.config.rotation,
+            );
+            reprojection.advance(&self.device, &self.queue, &mut self.ssaa, position);
+        }
+    }
+
+    /// Supersample the render into `surface_texture` and draw the overlay over it.
+    fn render_surface(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        surface_texture: &wgpu::SurfaceTexture,
+    ) {
+        let view = surface_texture.texture.create_view(&Default::default());
+        self.ssaa.render_pass(encoder, Some(view.clone()));
+        if let Some(hud) = &self.hud {
+            hud.render_pass(encoder, &view);
+        }
+    }
+
+    /// Show `text` over the surface with every font pixel `scale` pixels wide, or hide
+    /// the overlay with `None`.
+    ///
+    /// Does nothing when rendering offscreen.
+    pub fn set_hud(&mut self, text: Option<&str>, scale: u32) {
+        if let Some(hud) = &mut self.hud {
+            hud.write(&self.queue, text, scale);
+        }
+    }
+
+    /// Outline the rectangle between the surface pixels `from` and `to`, or hide it.
+    pub fn set_selection(&mut self, selection: Option<((f64, f64), (f64, f64))>) {
+        if let Some(hud) = &mut self.hud {
+            hud.select(&self.queue, selection);
+        }
+    }
+
+    fn hud_visible(&self) -> bool {
+        self.hud.as_ref().is_some_and(Hud::visible)
+    }
+
+    /// Read back the state of the output pixel at `(px, py)`, or its top left sample
+    /// with SSAA, and the point of the complex plane it renders.
+    ///
+    /// Assumes the rectilinear [`Mapping`].
+    pub fn inspect(&self, px: f64, py: f64) -> Inspection {
+        let sf = self.ssaa.ssaa_factor();
+        let (w, h) = self.dimensions();
+        let (sw, sh) = (w * sf, h * sf);
+        let ix = ((px * sf as f64) as usize).min(sw - 1);
+        let iy = ((py * sf as f64) as usize).min(sh - 1);
+        let state = self
+            .compute
+            .pixel_state(&self.device, &self.queue, iy * sw + ix);
+
+        // The screen offset of the pixel in `mandelbrot.wgsl`, rotated into the plane.
+        let u = (ix as f64 / sw as f64 * 2.0 - 1.0) * sw as f64 / sh as f64;
+        let v = (sh - iy) as f64 / sh as f64 * 2.0 - 1.0;
+        let (sin, cos) = (self.config.rotation as f64).to_radians().sin_cos();
+        let x = &self.x + &self.z * Float::from(u * cos - v * sin);
+        let y = &self.y + &self.z * Float::from(u * sin + v * cos);
+
+        // Continuous coloring, from `color` in `mandelbrot.wgsl`.
+        let escaped = state.finished == 1 && state.j < self.config.iterations as i32;
+        let smooth = self
+            .orbit
+            .point(state.k as usize)
+            .filter(|_| escaped)
+            .map(|(rx, ry, e)| {
+                let s = 2f64.powi(state.q);
+                let fx = rx as f64 * 2f64.powi(e) + s * state.dx as f64;
+                let fy = ry as f64 * 2f64.powi(e) + s * state.dy as f64;
+                let nu = ((fx * fx + fy * fy).log2() * 0.5).log2();
+                state.j as f64 + 1.0 - nu
+            });
+
+        Inspection {
+            x,
+            y,
+            state,
+            smooth,
+        }
+    }
+
+    /// Pixels left to render, as counted by the last step.
+    ///
+    /// [`Pipeline::force_step_mandelbrot`] only counts them while the overlay is shown.
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Time taken to compute the last reference orbit.
+    pub fn orbit_time(&self) -> Duration {
+        self.orbit_time
+    }
+
     /// Renders the mandelbrot into the offscreen buffer.
     pub fn render_output(&self) {
         let mut encoder = self
@@ -505,6 +718,21 @@ impl Pipeline {
         result
     }
 
+    /// Exact position of the current view.
+    pub fn position(&self) -> Position {
+        Position {
+            x: self.x.to_string(),
+            y: self.y.to_string(),
+            zoom: self.z.to_string(),
+            rotation: self.config.rotation,
+        }
+    }
+
+    /// Png text chunks recording the config and exact position of the current view.
+    pub fn png_metadata(&self) -> Vec<(String, String)> {
+        png_metadata(&self.config, &self.position())
+    }
+
     /// Write the current position and iterations for `frame`.
     pub fn log(&mut self, frame: usize) -> std::io::Result<()> {
         if let Some(log) = &mut self.log {
@@ -512,13 +740,104 @@ impl Pipeline {
             log.write_all(format!("x = \"{}\"\n", self.x).as_bytes())?;
             log.write_all(format!("y = \"{}\"\n", self.y).as_bytes())?;
             log.write_all(format!("zoom = \"{}\"\n", self.z).as_bytes())?;
-            log.write_all(format!("iterations = {}\n\n", self.config.iterations).as_bytes())?;
+            log.write_all(format!("rotation = {}\n", self.config.rotation).as_bytes())?;
+            log.write_all(format!("iterations = {}\n", self.config.iterations).as_bytes())?;
+            log.write_all(format!("precision = {}\n\n", self.precision).as_bytes())?;
             log.flush()?;
         }
         Ok(())
     }
 }
 
+/// Png text chunks recording `config` and the exact `position` of an image.
+pub fn png_metadata(config: &Config, position: &Position) -> Vec<(String, String)> {
+    vec![
+        (
+            PNG_CONFIG_KEYWORD.to_string(),
+            toml::to_string(config).expect("config serializes to toml"),
+        ),
+        (
+            PNG_POSITION_KEYWORD.to_string(),
+            toml::to_string(position).expect("position serializes to toml"),
+        ),
+    ]
+}
+
+/// Pixel read back by [`Pipeline::inspect`].
+pub struct Inspection {
+    /// Point of the complex plane the pixel renders.
+    pub x: Float,
+    pub y: Float,
+    pub state: PixelState,
+    /// Iteration count with the fraction used by continuous coloring, once the pixel
+    /// has escaped.
+    pub smooth: Option<f64>,
+}
+
+/// Frame entry written by [`Pipeline::log`].
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct LoggedFrame {
+    #[serde(skip)]
+    pub frame: usize,
+    pub x: String,
+    pub y: String,
+    pub zoom: String,
+    #[serde(default)]
+    pub rotation: f32,
+    pub iterations: usize,
+    /// Bits of precision the frame was rendered with.
+    #[serde(default)]
+    pub precision: u64,
+}
+
+/// Parse the frame entries written by [`Pipeline::log`].
+///
+/// Text after the blank line ending the last complete entry, and a malformed final
+/// entry, are treated as an interrupted write and ignored.
+pub fn read_log(log: &str) -> std::io::Result<Vec<LoggedFrame>> {
+    // A truncated field can still parse, so only the blank line ending an entry
+    // proves it was written completely.
+    let log = &log[..log.rfind("\n\n").map_or(0, |end| end + 2)];
+    let entries = log.split("[FRAME] ").skip(1).collect::<Vec<_>>();
+    let mut frames = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        let (frame, fields) = entry.split_once('\n').unwrap_or((entry, ""));
+        let parsed = frame
+            .trim()
+            .parse::<usize>()
+            .map_err(|err| err.to_string())
+            .and_then(|frame| {
+                toml::from_str::<LoggedFrame>(fields)
+                    .map(|logged| LoggedFrame { frame, ..logged })
+                    .map_err(|err| err.to_string())
+            });
+
+        match parsed {
+            Ok(logged) => frames.push(logged),
+            Err(_) if i + 1 == entries.len() => break,
+            Err(err) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid log entry `[FRAME] {}`: {err}", frame.trim()),
+                ));
+            }
+        }
+    }
+    Ok(frames)
+}
+
+/// Buffer that a `width` by `height` output is copied into, and its bytes per row.
+fn output_buffer(device: &wgpu::Device, width: usize, height: usize) -> (wgpu::Buffer, usize) {
+    let (bytes_per_row, buffer_size) = output_buffer_bytes_per_row_and_size(width, height);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    (buffer, bytes_per_row)
+}
+
 fn output_buffer_bytes_per_row_and_size(width: usize, height: usize) -> (usize, usize) {
     let bytes_per_pixel = 4;
     let align = 256;
@@ -528,3 +847,65 @@ fn output_buffer_bytes_per_row_and_size(width: usize, height: usize) -> (usize,
     let buffer_size = bpr * height;
     (bpr, buffer_size)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Log entry for `frame` as written by [`Pipeline::log`].
+    fn entry(frame: usize) -> String {
+        format!(
+            "[FRAME] {frame}\n\
+             x = \"-0.75\"\n\
+             y = \"0.1\"\n\
+             zoom = \"{}\"\n\
+             rotation = 0\n\
+             iterations = 1000\n\
+             precision = 128\n\n",
+            1.0 / (frame + 1) as f64
+        )
+    }
+
+    fn frames(log: &[LoggedFrame]) -> Vec<usize> {
+        log.iter().map(|logged| logged.frame).collect()
+    }
+
+    #[test]
+    fn reads_complete_log() {
+        let log = read_log(&(0..3).map(entry).collect::<String>()).unwrap();
+        assert_eq!(frames(&log), [0, 1, 2]);
+        assert_eq!(log[1].zoom, "0.5");
+        assert_eq!(log[1].iterations, 1000);
+        assert_eq!(log[1].precision, 128);
+    }
+
+    #[test]
+    fn reads_empty_log() {
+        assert!(read_log("").unwrap().is_empty());
+        assert!(read_log("\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn ignores_truncated_last_entry() {
+        let complete = (0..2).map(entry).collect::<String>();
+        let last = entry(2);
+        // Every prefix of the last entry, including those where the final field
+        // still parses, such as `precision = 1`.
+        for end in 0..last.len() {
+            let log = read_log(&format!("{complete}{}", &last[..end])).unwrap();
+            assert_eq!(frames(&log), [0, 1], "truncated at {end}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_middle_entry() {
+        let malformed = entry(1).replace("iterations = 1000", "iterations = many");
+        let log = format!("{}{malformed}{}", entry(0), entry(2));
+        let err = read_log(&log).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("`[FRAME] 1`"), "{err}");
+
+        let log = format!("{}[FRAME] one\n\n{}", entry(0), entry(2));
+        assert!(read_log(&log).is_err());
+    }
+}
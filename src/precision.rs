@@ -0,0 +1,83 @@
+use malachite_float::Float;
+
+/// Precision of an `f64`, below which coordinates are never rounded.
+pub const MIN_PRECISION: u64 = 53;
+
+/// Most bits a number may be parsed with, and the largest power of two it may be scaled
+/// by. This allows zooms of `2^-1048576`, far past what an orbit of
+/// [`crate::orbit::MAX_ITERATIONS`] iterations resolves, while bounding the time and
+/// memory a single number in a config can take.
+pub const MAX_PRECISION: u64 = 1 << 20;
+
+/// Bits kept beyond the size of a pixel, so perturbations are computed against an
+/// exact reference and the position survives repeated panning.
+const GUARD_BITS: u64 = 64;
+
+/// Bits the reference orbit keeps beyond the precision of the position, absorbing
+/// the rounding error of every iteration.
+const ORBIT_GUARD_BITS: u64 = 10;
+
+/// Bits needed to tell apart every pixel of a `width` by `height` view of `zoom`
+/// centred on `(x, y)`, or the `requested` precision if it is not 0.
+///
+/// Unlike the precision of the coordinates, this shrinks again when zooming out, which
+/// speeds up the reference orbit.
+pub fn position(
+    x: &Float,
+    y: &Float,
+    zoom: &Float,
+    width: usize,
+    height: usize,
+    requested: u64,
+) -> u64 {
+    if requested != 0 {
+        return requested;
+    }
+
+    // The view is `2 * zoom` high, so a pixel is `2 * zoom / height` in size and the
+    // widest coordinate has an exponent of at most `max(exp(x), exp(y), exp(zoom))`.
+    let exponent = |f: &Float| f.get_exponent().unwrap_or(0) as i64;
+    let pixels = width.max(height).max(1).ilog2() as i64 + 1;
+    let pixel = exponent(zoom) + 1 - pixels;
+    let widest = exponent(x).max(exponent(y)).max(exponent(zoom)).max(1);
+    ((widest - pixel).max(0) as u64 + GUARD_BITS).max(MIN_PRECISION)
+}
+
+/// Precision to compute with between the coordinates `floats`, keeping the guard bits
+/// of a position beyond the most precise of them.
+pub fn headroom<'a>(floats: impl IntoIterator<Item = &'a Float>) -> u64 {
+    floats
+        .into_iter()
+        .filter_map(Float::get_prec)
+        .max()
+        .unwrap_or(MIN_PRECISION)
+        .max(MIN_PRECISION)
+        + GUARD_BITS
+}
+
+/// Precision of the reference orbit around a position with `precision` bits.
+pub fn orbit(precision: u64) -> u64 {
+    precision + ORBIT_GUARD_BITS
+}
+
+/// Precision that represents a decimal with `digits` significant digits, counting
+/// the digits of the exponent, with bits to spare.
+pub fn decimal(digits: u64) -> u64 {
+    ((digits as f64 * std::f64::consts::LOG2_10).ceil() as u64)
+        .saturating_add(16)
+        .max(MIN_PRECISION)
+}
+
+/// Precision that represents a hex float with `digits` digits exactly.
+pub fn hex(digits: usize) -> u64 {
+    (4 * digits as u64).max(MIN_PRECISION)
+}
+
+/// Widen `f` to at least `precision` bits.
+///
+/// Coordinates are never rounded, so an exact position survives zooming out and back in.
+pub fn widen(f: &mut Float, precision: u64) {
+    if f.get_prec().is_some_and(|p| p < precision) {
+        f.set_prec(precision);
+    }
+}
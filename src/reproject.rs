@@ -0,0 +1,346 @@
+use crate::{byte_slice, ssaa::SsaaPipeline};
+use malachite::base::{num::conversion::traits::RoundingFrom, rounding_modes::RoundingMode};
+use malachite_float::Float;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct ReprojectUniform {
+    offset: [f32; 2],
+    scale: f32,
+    rotation_cos: f32,
+    rotation_sin: f32,
+    generation: f32,
+    _padding: [f32; 2],
+}
+
+/// Format of the fade textures, holding the weight of the render in each pixel and the
+/// generation of the position it belongs to.
+const FADE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Float;
+
+/// Position of a frame, as `(x, y, zoom, rotation)`.
+type Position = (Float, Float, Float, f32);
+
+/// Keeps the last frame shown under the render of a new position, moved to where it
+/// lies in the new view, and fades the pixels of the new render in as they finish.
+///
+/// Every frame, the finished pixels of the render are composited over the previous
+/// frame into one of two textures, which is supersampled in its place. When the position
+/// changes, that composite becomes the previous frame. How far each pixel has faded in
+/// is carried between frames in another pair of textures.
+pub struct Reprojection {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform: wgpu::Buffer,
+    /// Textures that hold the previous frame and the composite, in turns.
+    frames: [wgpu::TextureView; 2],
+    /// Textures that hold the fade weights of the last composite and the next, in turns.
+    fades: [wgpu::TextureView; 2],
+    /// Composites into the other frame and fade, with `frames[i]` as the previous frame
+    /// and `fades[j]` as the last fade.
+    bind_groups: [[wgpu::BindGroup; 2]; 2],
+    /// Index of the previous frame.
+    previous: usize,
+    /// Index of the last fade.
+    fade: usize,
+    /// Number of times the position has changed, so that fades of an older position
+    /// are not continued.
+    generation: u32,
+    /// Position of the render being composited, once there is one.
+    position: Option<Position>,
+}
+
+impl Reprojection {
+    pub fn new(
+        device: &wgpu::Device,
+        ssaa: &mut SsaaPipeline,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: std::mem::size_of::<ReprojectUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let texture = |binding, filterable| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable },
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                texture(0, true),
+                texture(1, true),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture(4, false),
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/reproject.wgsl"));
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[
+                    Some(wgpu::TextureFormat::Rgba32Float.into()),
+                    Some(FADE_FORMAT.into()),
+                ],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            cache: None,
+            multiview_mask: None,
+        });
+
+        let (frames, fades, bind_groups) = Self::frames(
+            device,
+            &bind_group_layout,
+            &sampler,
+            &uniform,
+            ssaa,
+            width,
+            height,
+        );
+        ssaa.set_source(device, &frames[1]);
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform,
+            frames,
+            fades,
+            bind_groups,
+            previous: 0,
+            fade: 0,
+            generation: 0,
+            position: None,
+        }
+    }
+
+    /// Reallocate the frames for a `width` by `height` output rendered into the
+    /// textures of `ssaa`, which must already be resized, forgetting the previous frame.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        ssaa: &mut SsaaPipeline,
+        width: usize,
+        height: usize,
+    ) {
+        (self.frames, self.fades, self.bind_groups) = Self::frames(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            &self.uniform,
+            ssaa,
+            width,
+            height,
+        );
+        self.previous = 0;
+        self.fade = 0;
+        self.position = None;
+        ssaa.set_source(device, &self.frames[1]);
+    }
+
+    /// Make the composite shown so far the previous frame of a render at `position`,
+    /// moving it to where it lies in the new view.
+    pub fn advance(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        ssaa: &mut SsaaPipeline,
+        position: Position,
+    ) {
+        let to_f64 = |f: Float| f64::rounding_from(&f, RoundingMode::Nearest).0;
+        let (x, y, zoom, rotation) = &position;
+        // Generations are stored as `f32`, which is exact up to 2^24.
+        self.generation = (self.generation + 1) % (1 << 24);
+        let generation = self.generation as f32;
+
+        // A screen offset `s` of the new view lies at `c + z * R(r) * s` in the plane,
+        // which the previous view shows at `R(-r') * (c - c') / z' + z / z' * R(r - r') * s`.
+        let uniform = match self.position.take() {
+            Some((px, py, pzoom, protation)) => {
+                let dx = to_f64((&*x - px) / &pzoom);
+                let dy = to_f64((&*y - py) / &pzoom);
+                let (sin, cos) = (-protation as f64).to_radians().sin_cos();
+                let (rsin, rcos) = ((rotation - protation) as f64).to_radians().sin_cos();
+                ReprojectUniform {
+                    offset: [(dx * cos - dy * sin) as f32, (dx * sin + dy * cos) as f32],
+                    scale: to_f64(&*zoom / pzoom) as f32,
+                    rotation_cos: rcos as f32,
+                    rotation_sin: rsin as f32,
+                    generation,
+                    _padding: [0.0; 2],
+                }
+            }
+            // Nothing has been shown yet, so the previous frame stays blank.
+            None => ReprojectUniform {
+                offset: [f32::INFINITY; 2],
+                scale: 1.0,
+                rotation_cos: 1.0,
+                rotation_sin: 0.0,
+                generation,
+                _padding: [0.0; 2],
+            },
+        };
+        queue.write_buffer(&self.uniform, 0, byte_slice(&[uniform]));
+
+        self.previous = 1 - self.previous;
+        ssaa.set_source(device, &self.frames[1 - self.previous]);
+        self.position = Some(position);
+    }
+
+    /// Composite the finished pixels of the render over the previous frame, fading
+    /// each further in.
+    pub fn render_pass(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let fade = self.fade;
+        self.fade = 1 - fade;
+
+        let attachment = |view| {
+            Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })
+        };
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[
+                attachment(&self.frames[1 - self.previous]),
+                attachment(&self.fades[1 - fade]),
+            ],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_groups[self.previous][fade], &[]);
+        rpass.draw(0..3, 0..1);
+    }
+
+    /// Create the two frames and fades at the size of the render target of `ssaa`, and
+    /// the bind groups that composite into each.
+    fn frames(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        uniform: &wgpu::Buffer,
+        ssaa: &SsaaPipeline,
+        width: usize,
+        height: usize,
+    ) -> (
+        [wgpu::TextureView; 2],
+        [wgpu::TextureView; 2],
+        [[wgpu::BindGroup; 2]; 2],
+    ) {
+        let sf = ssaa.ssaa_factor();
+        let size = wgpu::Extent3d {
+            width: (width * sf) as u32,
+            height: (height * sf) as u32,
+            depth_or_array_layers: 1,
+        };
+        let frame = |format| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    label: None,
+                    view_formats: &[],
+                })
+                .create_view(&Default::default())
+        };
+        let frames = [
+            frame(wgpu::TextureFormat::Rgba32Float),
+            frame(wgpu::TextureFormat::Rgba32Float),
+        ];
+        let fades = [frame(FADE_FORMAT), frame(FADE_FORMAT)];
+
+        let bind_group = |previous: &wgpu::TextureView, fade: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(ssaa.render_target()),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(previous),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: uniform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(fade),
+                    },
+                ],
+            })
+        };
+        let bind_groups =
+            [0, 1].map(|previous| [0, 1].map(|fade| bind_group(&frames[previous], &fades[fade])));
+
+        (frames, fades, bind_groups)
+    }
+}
@@ -0,0 +1,69 @@
+struct Hud {
+    columns: u32,
+    rows: u32,
+    scale: u32,
+    has_selection: u32,
+    // Corners of the selection in pixels, as `(min_x, min_y, max_x, max_y)`.
+    selection: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> hud: Hud;
+@group(0) @binding(1) var<storage, read> text: array<u32>;
+@group(0) @binding(2) var<storage, read> font: array<u32>;
+
+const GLYPH_SIZE: vec2<i32> = vec2(5, 7);
+// Glyphs are spaced by a column and lines by two rows.
+const CELL_SIZE: vec2<i32> = vec2(6, 9);
+// Distance of the panel from the corner, and of the text from the edge of the panel.
+const MARGIN: i32 = 2;
+const PADDING: i32 = 3;
+
+@vertex
+fn vs_main(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((id << 1u) & 2u), f32(id & 2u));
+    return vec4(uv * 2.0 + vec2(-1.0, -1.0), 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let text = text_color(position.xy);
+    if text.a > 0.0 {
+        return text;
+    }
+
+    let start = hud.selection.xy;
+    let end = hud.selection.zw;
+    if hud.has_selection == 0u || any(position.xy < start) || any(position.xy > end) {
+        discard;
+    }
+    // The outline is a font pixel wide, over a faint fill.
+    let edge = min(position.xy - start, end - position.xy);
+    let outline = any(edge < vec2(f32(hud.scale)));
+    return select(vec4(1.0, 1.0, 1.0, 0.15), vec4(1.0, 1.0, 1.0, 0.9), outline);
+}
+
+// Color of the text panel at `position`, transparent outside of it.
+fn text_color(position: vec2<f32>) -> vec4<f32> {
+    // Position in font pixels from the top left corner of the text.
+    let p = vec2<i32>(position) / i32(hud.scale) - MARGIN - PADDING;
+    let size = vec2(i32(hud.columns), i32(hud.rows)) * CELL_SIZE - vec2(1, 2);
+    if hud.rows == 0u || any(p < vec2(-PADDING)) || any(p >= size + PADDING) {
+        return vec4(0.0);
+    }
+
+    let background = vec4(0.0, 0.0, 0.0, 0.6);
+    if any(p < vec2(0)) || any(p >= size) {
+        return background;
+    }
+
+    let cell = p / CELL_SIZE;
+    let pixel = p % CELL_SIZE;
+    if any(pixel >= GLYPH_SIZE) {
+        return background;
+    }
+
+    let glyph = text[u32(cell.y) * hud.columns + u32(cell.x)];
+    let row = font[glyph * u32(GLYPH_SIZE.y) + u32(pixel.y)];
+    let lit = (row >> u32(GLYPH_SIZE.x - 1 - pixel.x)) & 1u;
+    return select(background, vec4(1.0), lit == 1u);
+}
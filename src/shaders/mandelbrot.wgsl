@@ -4,7 +4,11 @@ struct MandelbrotUniform {
 	batch_iter: i32,
     palette_len: f32,
 	color_scale: f32,
+	palette_offset: f32,
 	color_mode: i32,
+	mapping: i32,
+	rotation_cos: f32,
+	rotation_sin: f32,
 }
 
 struct OrbitUniform {
@@ -42,11 +46,25 @@ override SWAP_CHANNELS: bool = false;
 fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local_id: u32) {
     let sz = textureDimensions(output);
     if (id.x >= sz.x || id.y >= sz.y) { return; }
-    let aspect = f32(sz.x) / f32(sz.y);
-    var uv = vec2<f32>(f32(id.x), f32(sz.y - id.y)) / vec2<f32>(sz.xy) * 2.0 - 1.0;
-	uv.x *= aspect;
+	var delta: vec2<f32>;
+	if (args.mapping == 1) {
+		// Exponential map: the bottom row is a ring with a radius of the zoom and every
+		// row above grows the radius by one column's worth of arc length.
+		let tau = 6.28318530718;
+		let theta = tau * (f32(id.x) + 0.5) / f32(sz.x);
+		let rho = tau * (f32(sz.y - id.y) - 0.5) / f32(sz.x);
+		delta = exp(rho) * vec2(cos(theta), sin(theta)) * args.zm * 2.0;
+	} else {
+		let aspect = f32(sz.x) / f32(sz.y);
+		var uv = vec2<f32>(f32(id.x), f32(sz.y - id.y)) / vec2<f32>(sz.xy) * 2.0 - 1.0;
+		uv.x *= aspect;
+		delta = uv * args.zm * 2.0;
+	}
 	let state_index = id.y * sz.x + id.x;
-    textureStore(output, id.xy, mandelbrot(state_index, uv * args.zm * 2.0));
+	var color = mandelbrot(state_index, delta);
+	// The alpha marks finished pixels, which replace the frame reprojected under them.
+	color.a = f32(states[state_index].finished);
+    textureStore(output, id.xy, color);
 	if (states[state_index].finished == 0u) {
 		atomicAdd(&remaining, 1u);
     }
@@ -54,7 +72,13 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_
 
 // I am not going to pretend to understand this code: 
 // https://github.com/HastingsGreer/mandeljs/blob/7bb12c6ee2214e4eea82a30498de85823b3be474/main.js#L198
-fn mandelbrot(state_index: u32, delta: vec2<f32>) -> vec4<f32> {
+fn mandelbrot(state_index: u32, screen_delta: vec2<f32>) -> vec4<f32> {
+	// The series coefficients are rotated with the view, so the series is evaluated
+	// with the screen delta and only the per-iteration delta is rotated.
+	let delta = vec2(
+		screen_delta.x * args.rotation_cos - screen_delta.y * args.rotation_sin,
+		screen_delta.x * args.rotation_sin + screen_delta.y * args.rotation_cos,
+	);
 	var state = states[state_index];
 
 	if (state.finished == 1u) {
@@ -73,8 +97,8 @@ fn mandelbrot(state_index: u32, delta: vec2<f32>) -> vec4<f32> {
         k = orbit.polylim;
         j = k;
         
-        let dcx_init = delta.x;
-        let dcy_init = delta.y;
+        let dcx_init = screen_delta.x;
+        let dcy_init = screen_delta.y;
         let sqrx = dcx_init * dcx_init - dcy_init * dcy_init;
         let sqry = 2.0 * dcx_init * dcy_init;
         let cux = dcx_init * sqrx - dcy_init * sqry;
@@ -203,7 +227,7 @@ fn color(state: OrbitState) -> vec4<f32> {
 fn wave(iteration: f32) -> vec4<f32> {
 	var count = iteration;
 	let period = 64.0 * args.color_scale;
-	let mo = 0.0;
+	let mo = args.palette_offset * 2.0 * 3.14159265;
 
 	var rp = 1.0;
 	var gp = 1.0;
@@ -239,7 +263,7 @@ fn wave(iteration: f32) -> vec4<f32> {
 }
 
 fn sample(x: f32) -> vec4<f32> {
-	let uv = vec2(x, 0.5);
+	let uv = vec2(x + args.palette_offset, 0.5);
 	let rgb = textureSampleLevel(palette, palette_sampler, uv, 0.0).rgb;
     return vec4(select(rgb.bgr, rgb, SWAP_CHANNELS), 1.0);
 }
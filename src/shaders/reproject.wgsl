@@ -0,0 +1,68 @@
+struct Reprojection {
+    // Maps a screen offset of the current view to the previous view.
+    offset: vec2<f32>,
+    scale: f32,
+    rotation_cos: f32,
+    rotation_sin: f32,
+    // Advances of the view so far, which tags the fade weights of the same view.
+    generation: f32,
+}
+
+struct Composite {
+    @location(0) color: vec4<f32>,
+    // Weight of the new render and the generation it belongs to.
+    @location(1) fade: vec2<f32>,
+}
+
+// Frames over which a finished pixel fades in over the reprojected frame.
+const FADE_FRAMES: f32 = 8.0;
+
+@group(0) @binding(0) var current: texture_2d<f32>;
+@group(0) @binding(1) var previous: texture_2d<f32>;
+@group(0) @binding(2) var frame_sampler: sampler;
+@group(0) @binding(3) var<uniform> reprojection: Reprojection;
+@group(0) @binding(4) var last_fade: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((id << 1u) & 2u), f32(id & 2u));
+    return vec4(uv * 2.0 + vec2(-1.0, -1.0), 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> Composite {
+    let size = vec2<f32>(textureDimensions(current));
+    let aspect = size.x / size.y;
+
+    // Screen offset of the pixel, as in `mandelbrot.wgsl`.
+    let id = position.xy - 0.5;
+    var s = vec2(id.x, size.y - id.y) / size * 2.0 - 1.0;
+    s.x *= aspect;
+
+    let r = reprojection;
+    var p = vec2(
+        s.x * r.rotation_cos - s.y * r.rotation_sin,
+        s.x * r.rotation_sin + s.y * r.rotation_cos,
+    ) * r.scale + r.offset;
+    p.x /= aspect;
+    let previous_id = vec2(p.x + 1.0, 1.0 - p.y) * 0.5 * size;
+    let uv = (previous_id + 0.5) / size;
+
+    // Outside of the previous frame is unknown, so it is left black.
+    var color = vec4(0.0, 0.0, 0.0, 1.0);
+    if all(uv >= vec2(0.0)) && all(uv <= vec2(1.0)) {
+        color = textureSampleLevel(previous, frame_sampler, uv, 0.0);
+    }
+
+    // The alpha of a pixel is 1 once it has finished, from when it fades in. Weights
+    // left from before the view last moved start over.
+    let pixel = textureLoad(current, vec2<u32>(id), 0);
+    let last = textureLoad(last_fade, vec2<u32>(id), 0).xy;
+    let faded = select(0.0, last.x, last.y == r.generation);
+    let weight = select(0.0, min(faded + 1.0 / FADE_FRAMES, 1.0), pixel.a > 0.0);
+
+    var out: Composite;
+    out.color = vec4(mix(color.rgb, pixel.rgb, weight), 1.0);
+    out.fade = vec2(weight, r.generation);
+    return out;
+}
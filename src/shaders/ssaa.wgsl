@@ -18,6 +18,7 @@ override GAMMA_CORRECT: bool = false;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-	let color = textureSample(texture, texture_sampler, in.uv);
-	return select(color, vec4(pow(color.rgb, vec3<f32>(1.0 / 2.2)), color.a), GAMMA_CORRECT);
+	let color = textureSample(texture, texture_sampler, in.uv).rgb;
+	// The alpha of the render only marks finished pixels, so the output is opaque.
+	return vec4(select(color, pow(color, vec3<f32>(1.0 / 2.2)), GAMMA_CORRECT), 1.0);
 }
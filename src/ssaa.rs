@@ -6,9 +6,12 @@ const SSAA_SAMPLES: u32 = 2;
 /// then this operation performs supersampling.
 pub struct SsaaPipeline {
     pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
     bind_group: wgpu::BindGroup,
     src_view: wgpu::TextureView,
     dst: Option<(wgpu::Texture, wgpu::TextureView)>,
+    format: wgpu::TextureFormat,
     enabled: bool,
 }
 
@@ -21,29 +24,6 @@ impl SsaaPipeline {
         height: usize,
         enabled: bool,
     ) -> Self {
-        let dst = if render_to_surface {
-            None
-        } else {
-            let dst_desc = wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: width as u32,
-                    height: height as u32,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
-                label: None,
-                view_formats: &[],
-            };
-            let dst = device.create_texture(&dst_desc);
-            let dst_view = dst.create_view(&Default::default());
-            Some((dst, dst_view))
-        };
-
-        let ssaa_samples = if enabled { SSAA_SAMPLES } else { 1 };
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
             entries: &[
@@ -66,44 +46,14 @@ impl SsaaPipeline {
             ],
         });
 
-        let src_desc = wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: width as u32 * ssaa_samples,
-                height: height as u32 * ssaa_samples,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba32Float,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::STORAGE_BINDING
-                | wgpu::TextureUsages::COPY_SRC,
-            label: None,
-            view_formats: &[],
-        };
-        let src = device.create_texture(&src_desc);
-        let src_view = src.create_view(&Default::default());
-
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&src_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
+        let (src_view, dst) =
+            Self::textures(device, format, render_to_surface, width, height, enabled);
+        let bind_group = Self::bind_group(device, &bind_group_layout, &src_view, &sampler);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             bind_group_layouts: &[&bind_group_layout],
@@ -139,13 +89,119 @@ impl SsaaPipeline {
 
         Self {
             pipeline,
+            bind_group_layout,
+            sampler,
             bind_group,
             src_view,
             dst,
+            format,
             enabled,
         }
     }
 
+    /// Reallocate the textures for a `width` by `height` output, supersampled if `enabled`.
+    pub fn resize(&mut self, device: &wgpu::Device, width: usize, height: usize, enabled: bool) {
+        let render_to_surface = self.dst.is_none();
+        self.enabled = enabled;
+        let (src_view, dst) = Self::textures(
+            device,
+            self.format,
+            render_to_surface,
+            width,
+            height,
+            self.enabled,
+        );
+        self.bind_group =
+            Self::bind_group(device, &self.bind_group_layout, &src_view, &self.sampler);
+        self.src_view = src_view;
+        self.dst = dst;
+    }
+
+    /// Create the texture the mandelbrot is rendered to and, unless rendering to a
+    /// surface, the texture it is supersampled into.
+    fn textures(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        render_to_surface: bool,
+        width: usize,
+        height: usize,
+        enabled: bool,
+    ) -> (
+        wgpu::TextureView,
+        Option<(wgpu::Texture, wgpu::TextureView)>,
+    ) {
+        let dst = if render_to_surface {
+            None
+        } else {
+            let dst_desc = wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width: width as u32,
+                    height: height as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                label: None,
+                view_formats: &[],
+            };
+            let dst = device.create_texture(&dst_desc);
+            let dst_view = dst.create_view(&Default::default());
+            Some((dst, dst_view))
+        };
+
+        let ssaa_samples = if enabled { SSAA_SAMPLES } else { 1 };
+        let src_desc = wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: width as u32 * ssaa_samples,
+                height: height as u32 * ssaa_samples,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            label: None,
+            view_formats: &[],
+        };
+        let src = device.create_texture(&src_desc);
+        let src_view = src.create_view(&Default::default());
+
+        (src_view, dst)
+    }
+
+    fn bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        src_view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(src_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Supersample `view` in place of [`Self::render_target`], until the next resize.
+    pub fn set_source(&mut self, device: &wgpu::Device, view: &wgpu::TextureView) {
+        self.bind_group = Self::bind_group(device, &self.bind_group_layout, view, &self.sampler);
+    }
+
     pub fn ssaa_factor(&self) -> usize {
         if self.enabled {
             SSAA_SAMPLES as usize
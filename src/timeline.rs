@@ -0,0 +1,422 @@
+use crate::{
+    config::{Config, Preset},
+    error::Error,
+    float_from_str, interpolate_zoom,
+    orbit::MAX_ITERATIONS,
+    pipeline::Pipeline,
+    precision,
+};
+use malachite_float::Float;
+
+/// Animation made of keyframes that are interpolated by [`crate::render_mp4`].
+///
+/// ```toml
+/// [[keyframe]]
+/// time = 0.0
+/// zoom = "2.0"
+/// easing = "ease_in_out"
+///
+/// [[keyframe]]
+/// time = 20.0
+/// x = "-0.743643887037158704752191506114774"
+/// y = "0.131825904205311970493132056385139"
+/// zoom = "1e-20"
+/// iterations = 100000
+/// palette = "inferno"
+/// ```
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Timeline {
+    #[serde(rename = "keyframe")]
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// View and color parameters at a point in time.
+///
+/// Omitted fields hold the value of the previous keyframe, or of the base config
+/// for the first keyframe.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct Keyframe {
+    /// Time in seconds.
+    pub time: f64,
+    pub x: Option<String>,
+    pub y: Option<String>,
+    pub zoom: Option<String>,
+    /// Rotation in degrees.
+    pub rotation: Option<f32>,
+    pub iterations: Option<usize>,
+    pub color_scale: Option<f32>,
+    pub palette_offset: Option<f32>,
+    /// Palettes are not blended, the palette switches when the keyframe is reached.
+    pub palette: Option<Preset>,
+    /// Easing of the transition into the next keyframe.
+    pub easing: Easing,
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Hold the keyframe until the next one is reached.
+    #[value(skip)]
+    Hold,
+}
+
+impl Easing {
+    /// Map the linear progress `t` in `[0, 1]` onto the curve.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Self::Hold => 0.0,
+        }
+    }
+}
+
+impl Timeline {
+    pub fn from_path(path: &str) -> Result<Self, Error> {
+        let toml = std::fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        toml::from_str(&toml).map_err(|source| Error::Parse {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// Fill in every keyframe from the previous one, starting with `base`, and check
+    /// that each holds values that can be rendered.
+    pub fn resolve(&self, base: &Config) -> Result<ResolvedTimeline, Error> {
+        if self.keyframes.is_empty() {
+            return Err(Error::invalid("keyframe", "the timeline has no keyframes"));
+        }
+
+        let mut keys = Vec::<ResolvedKeyframe>::with_capacity(self.keyframes.len());
+        for (i, keyframe) in self.keyframes.iter().enumerate() {
+            let invalid = |field: &str, message: String| {
+                Error::invalid(&format!("keyframe[{i}].{field}"), message)
+            };
+            let previous = keys.last();
+            if !keyframe.time.is_finite() {
+                return Err(invalid("time", "must be a finite number".to_string()));
+            }
+            if previous.is_some_and(|previous| keyframe.time < previous.time) {
+                return Err(invalid(
+                    "time",
+                    format!("is earlier than keyframe {}", i - 1),
+                ));
+            }
+
+            let float =
+                |field: &str, value: &Option<String>, inherited: Option<&Float>, default: &str| {
+                    let value = match (value, inherited) {
+                        (Some(value), _) => value,
+                        (None, Some(inherited)) => return Ok(inherited.clone()),
+                        (None, None) => default,
+                    };
+                    float_from_str(value)
+                        .ok_or_else(|| invalid(field, format!("`{value}` is not a number")))
+                };
+            let key = ResolvedKeyframe {
+                time: keyframe.time,
+                x: float("x", &keyframe.x, previous.map(|p| &p.x), &base.x)?,
+                y: float("y", &keyframe.y, previous.map(|p| &p.y), &base.y)?,
+                zoom: float(
+                    "zoom",
+                    &keyframe.zoom,
+                    previous.map(|p| &p.zoom),
+                    &base.zoom,
+                )?,
+                rotation: keyframe
+                    .rotation
+                    .or(previous.map(|p| p.rotation))
+                    .unwrap_or(base.rotation),
+                iterations: keyframe
+                    .iterations
+                    .or(previous.map(|p| p.iterations))
+                    .unwrap_or(base.iterations),
+                color_scale: keyframe
+                    .color_scale
+                    .or(previous.map(|p| p.color_scale))
+                    .unwrap_or(base.color_scale),
+                palette_offset: keyframe
+                    .palette_offset
+                    .or(previous.map(|p| p.palette_offset))
+                    .unwrap_or(base.palette_offset),
+                palette: keyframe
+                    .palette
+                    .or(previous.map(|p| p.palette))
+                    .unwrap_or(base.palette),
+                easing: keyframe.easing,
+            };
+
+            if key.zoom <= 0u32 {
+                return Err(invalid("zoom", "must be greater than 0".to_string()));
+            }
+            if key.iterations == 0 || key.iterations > MAX_ITERATIONS {
+                return Err(invalid(
+                    "iterations",
+                    format!("must be between 1 and {MAX_ITERATIONS}, the capacity of the orbit"),
+                ));
+            }
+            if !key.color_scale.is_finite() || key.color_scale == 0.0 {
+                return Err(invalid(
+                    "color_scale",
+                    "must be a finite, non-zero number".to_string(),
+                ));
+            }
+            if !key.palette_offset.is_finite() {
+                return Err(invalid(
+                    "palette_offset",
+                    "must be a finite number".to_string(),
+                ));
+            }
+            if !key.rotation.is_finite() {
+                return Err(invalid("rotation", "must be a finite number".to_string()));
+            }
+            keys.push(key);
+        }
+
+        Ok(ResolvedTimeline { keys })
+    }
+}
+
+/// [`Timeline`] with every keyframe fully specified.
+pub struct ResolvedTimeline {
+    keys: Vec<ResolvedKeyframe>,
+}
+
+struct ResolvedKeyframe {
+    time: f64,
+    x: Float,
+    y: Float,
+    zoom: Float,
+    rotation: f32,
+    iterations: usize,
+    color_scale: f32,
+    palette_offset: f32,
+    palette: Preset,
+    easing: Easing,
+}
+
+impl ResolvedTimeline {
+    /// Time of the last keyframe in seconds.
+    pub fn duration(&self) -> f64 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    /// Number of frames needed to play the timeline at `fps`, including the last keyframe.
+    pub fn frames(&self, fps: usize) -> usize {
+        (self.duration() * fps as f64).round() as usize + 1
+    }
+
+    /// Move `pipeline` to `time` seconds into the timeline.
+    pub fn apply(&self, pipeline: &mut Pipeline, time: f64) {
+        let i = self
+            .keys
+            .partition_point(|key| key.time <= time)
+            .saturating_sub(1);
+        let a = &self.keys[i];
+        let b = self.keys.get(i + 1).unwrap_or(a);
+        let t = if b.time > a.time {
+            a.easing
+                .apply(((time - a.time) / (b.time - a.time)).clamp(0.0, 1.0))
+        } else {
+            0.0
+        };
+        let lerp = |a: f32, b: f32| a + (b - a) * t as f32;
+
+        let iterations = (a.iterations as f64 + (b.iterations as f64 - a.iterations as f64) * t)
+            .round() as usize;
+        let color_scale = lerp(a.color_scale, b.color_scale);
+        let palette_offset = lerp(a.palette_offset, b.palette_offset);
+        let rotation = lerp(a.rotation, b.rotation);
+        let changed = pipeline.read_config(|config| {
+            config.iterations != iterations
+                || config.color_scale != color_scale
+                || config.palette_offset != palette_offset
+                || config.palette != a.palette
+        });
+        if changed {
+            pipeline.write_config(|config| {
+                config.iterations = iterations;
+                config.color_scale = color_scale;
+                config.palette_offset = palette_offset;
+                config.palette = a.palette;
+            });
+        }
+
+        pipeline.set_rotation(rotation);
+
+        let (x, y, z) = interpolate_position(a, b, t);
+        pipeline.write_position(|px, py, pz| {
+            *px = x;
+            *py = y;
+            *pz = z;
+        });
+    }
+}
+
+/// Interpolate the zoom in log space and pan in proportion to the change in zoom,
+/// which keeps the motion on screen uniform while zooming.
+fn interpolate_position(
+    a: &ResolvedKeyframe,
+    b: &ResolvedKeyframe,
+    t: f64,
+) -> (Float, Float, Float) {
+    if t <= 0.0 {
+        return (a.x.clone(), a.y.clone(), a.zoom.clone());
+    }
+    if t >= 1.0 {
+        return (b.x.clone(), b.y.clone(), b.zoom.clone());
+    }
+
+    // Every operation is performed with enough headroom to resolve a pixel at the
+    // deeper of the two zooms.
+    let prec = precision::headroom([&a.x, &a.y, &a.zoom, &b.x, &b.y, &b.zoom]);
+    let with_prec = |f: &Float| {
+        let mut f = f.clone();
+        f.set_prec(prec);
+        f
+    };
+    let (xa, ya, za) = (with_prec(&a.x), with_prec(&a.y), with_prec(&a.zoom));
+    let (xb, yb, zb) = (with_prec(&b.x), with_prec(&b.y), with_prec(&b.zoom));
+
+    let z = interpolate_zoom(&za, &zb, t);
+
+    let w = if za == zb {
+        with_prec(&Float::from(t))
+    } else {
+        (&z - &za) / (&zb - &za)
+    };
+    let x = &xa + (&xb - &xa) * &w;
+    let y = &ya + (&yb - &ya) * &w;
+    (x, y, z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(keyframes: Vec<Keyframe>) -> Timeline {
+        Timeline { keyframes }
+    }
+
+    fn field(err: Error) -> String {
+        match err {
+            Error::Invalid { field, .. } => field,
+            err => panic!("expected an invalid field, got {err}"),
+        }
+    }
+
+    #[test]
+    fn resolve_inherits_from_previous_keyframe() {
+        let base = Config::default();
+        let resolved = timeline(vec![
+            Keyframe {
+                color_scale: Some(2.0),
+                ..Default::default()
+            },
+            Keyframe {
+                time: 1.0,
+                ..Default::default()
+            },
+        ])
+        .resolve(&base)
+        .unwrap();
+        assert_eq!(resolved.keys[1].color_scale, 2.0);
+        assert_eq!(resolved.keys[1].iterations, base.iterations);
+    }
+
+    #[test]
+    fn resolve_names_the_invalid_keyframe_field() {
+        let base = Config::default();
+        let second = |keyframe: Keyframe| {
+            let first = Keyframe::default();
+            let second = Keyframe {
+                time: 1.0,
+                ..keyframe
+            };
+            field(timeline(vec![first, second]).resolve(&base).unwrap_err())
+        };
+
+        let cases = [
+            (
+                Keyframe {
+                    color_scale: Some(f32::NAN),
+                    ..Default::default()
+                },
+                "keyframe[1].color_scale",
+            ),
+            (
+                Keyframe {
+                    palette_offset: Some(f32::INFINITY),
+                    ..Default::default()
+                },
+                "keyframe[1].palette_offset",
+            ),
+            (
+                Keyframe {
+                    rotation: Some(f32::NAN),
+                    ..Default::default()
+                },
+                "keyframe[1].rotation",
+            ),
+            (
+                Keyframe {
+                    zoom: Some("-1".to_string()),
+                    ..Default::default()
+                },
+                "keyframe[1].zoom",
+            ),
+            (
+                Keyframe {
+                    x: Some("left".to_string()),
+                    ..Default::default()
+                },
+                "keyframe[1].x",
+            ),
+            (
+                Keyframe {
+                    iterations: Some(0),
+                    ..Default::default()
+                },
+                "keyframe[1].iterations",
+            ),
+        ];
+        for (keyframe, expected) in cases {
+            assert_eq!(second(keyframe), expected);
+        }
+
+        let nan_time = timeline(vec![Keyframe {
+            time: f64::NAN,
+            ..Default::default()
+        }]);
+        assert_eq!(
+            field(nan_time.resolve(&base).unwrap_err()),
+            "keyframe[0].time"
+        );
+        assert_eq!(
+            field(timeline(vec![]).resolve(&base).unwrap_err()),
+            "keyframe"
+        );
+    }
+}
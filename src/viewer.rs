@@ -1,10 +1,17 @@
-use crate::{config::Config, pipeline::Pipeline};
+use crate::{
+    config::{ColorMode, Config, Preset},
+    history::{Bookmarks, History, View},
+    orbit::MAX_ITERATIONS,
+    pipeline::{Inspection, Pipeline},
+};
 use glazer::winit::{
     event::{DeviceEvent, ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
-    keyboard::{KeyCode, PhysicalKey},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+    window::Fullscreen,
 };
-use malachite::base::num::basic::traits::{NegativeOne, One, OneHalf, Two};
+use malachite::base::{num::conversion::traits::RoundingFrom, rounding_modes::RoundingMode};
 use malachite_float::Float;
+use std::f64::consts::LOG10_2;
 
 pub fn run(memory: Memory) -> ! {
     let width = memory.config.width;
@@ -17,21 +24,364 @@ pub struct Memory {
     pipeline_builder: Option<crate::pipeline::PipelineBuilder>,
     pipeline: Option<Pipeline>,
     config: Config,
+    /// Config as loaded, whose size is used for saved views and snapshots.
+    render_config: Config,
     cursor_x: f64,
     cursor_y: f64,
+    /// Window position where the left button was pressed, while it is held.
+    drag: Option<(f64, f64)>,
+    pan_speed: f64,
+    zoom: Option<ZoomAnimation>,
+    /// Height of the window in logical pixels, which the zoom is scaled with on resize.
+    logical_height: f64,
+    hud: bool,
+    /// Left clicks report the pixel under the cursor, which the overlay follows,
+    /// instead of zooming.
+    inspect: bool,
+    /// Overlay text of the last pixel inspected and what it was read back at.
+    inspected: Option<(Inspected, String)>,
+    modifiers: ModifiersState,
+    history: History,
+    bookmarks: Bookmarks,
+    /// Frames since the view was last navigated, see [`Memory::checkpoint`].
+    idle_frames: u32,
 }
 
 impl Memory {
     pub fn from_config(config: Config) -> Self {
         Self {
+            render_config: config.clone(),
             config,
             cursor_x: 0.0,
             cursor_y: 0.0,
+            drag: None,
+            pan_speed: 0.1,
+            zoom: None,
+            logical_height: 0.0,
+            hud: false,
+            inspect: false,
+            inspected: None,
+            modifiers: ModifiersState::empty(),
+            history: History::default(),
+            bookmarks: Bookmarks::default(),
+            idle_frames: HISTORY_IDLE_FRAMES,
             pipeline: None,
             #[cfg(target_arch = "wasm32")]
             pipeline_builder: None,
         }
     }
+
+    /// Pan by `speed` times the view height for every key press.
+    pub fn with_pan_speed(mut self, speed: f64) -> Self {
+        self.pan_speed = speed;
+        self
+    }
+
+    /// Show the overlay with the location and render progress from the start.
+    pub fn with_hud(mut self, hud: bool) -> Self {
+        self.hud = hud;
+        self
+    }
+
+    /// Persist the bookmarks to `path`, loading the ones already saved there.
+    pub fn with_bookmarks(mut self, path: std::path::PathBuf) -> Self {
+        self.bookmarks = Bookmarks::load(path);
+        self
+    }
+
+    /// Current view of the pipeline, if it has been created.
+    fn view(&mut self) -> Option<View> {
+        let pipeline = self.pipeline.as_mut()?;
+        let config = pipeline.read_config(Config::clone);
+        let (x, y, zoom) = pipeline.read_position(|x, y, z| (x.clone(), y.clone(), z.clone()));
+        Some(View { x, y, zoom, config })
+    }
+
+    /// Record the view to undo before navigating away from it.
+    ///
+    /// Navigation within [`HISTORY_IDLE_FRAMES`] of the last, such as the events of a
+    /// single scroll, is undone in one step.
+    fn checkpoint(&mut self) {
+        let idle = self.idle_frames >= HISTORY_IDLE_FRAMES;
+        self.idle_frames = 0;
+        if idle && let Some(view) = self.view() {
+            self.history.push(view);
+        }
+    }
+
+    /// Jump to `view`, keeping the size of the window and its supersampling.
+    fn restore(&mut self, view: View) {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return;
+        };
+        self.zoom = None;
+        self.idle_frames = HISTORY_IDLE_FRAMES;
+        pipeline.write_config(|config| {
+            *config = Config {
+                width: config.width,
+                height: config.height,
+                ssaa: config.ssaa,
+                ..view.config
+            };
+        });
+        pipeline.write_position(|x, y, z| {
+            *x = view.x;
+            *y = view.y;
+            *z = view.zoom;
+        });
+        self.config = pipeline.read_config(Config::clone);
+    }
+
+    /// Change the render parameters, keeping the position navigated to.
+    fn write_config(&mut self, f: impl FnOnce(&mut Config)) {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return;
+        };
+        pipeline.write_config(f);
+        self.config = pipeline.read_config(Config::clone);
+    }
+
+    /// Ease the zoom by `factor` toward the window position `(px, py)`, which stays put.
+    fn zoom_toward(&mut self, factor: f64, px: f64, py: f64) {
+        self.checkpoint();
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return;
+        };
+        let target = match self.zoom.take() {
+            Some(zoom) => zoom.target,
+            None => pipeline.read_position(|_, _, z| z.clone()),
+        };
+        self.zoom = Some(ZoomAnimation {
+            target: target * Float::from(factor),
+            anchor: (px, py),
+        });
+    }
+}
+
+impl Memory {
+    /// Zoom so the box between the window positions `from` and `to` fills the window,
+    /// or with `out`, so the window shrinks into the box.
+    fn zoom_to_box(
+        &mut self,
+        window: &glazer::winit::window::Window,
+        from: (f64, f64),
+        to: (f64, f64),
+        out: bool,
+    ) {
+        self.checkpoint();
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return;
+        };
+        self.zoom = None;
+
+        let size = window.inner_size();
+        let (w, h) = (size.width as f64, size.height as f64);
+        let box_w = (from.0 - to.0).abs().max(1.0);
+        let box_h = (from.1 - to.1).abs().max(1.0);
+        let (cx, cy) = ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+        let rotation = pipeline.read_config(|config| config.rotation);
+        let (dx, dy) = plane_offset(window, rotation, cx, cy);
+
+        pipeline.write_position(|x, y, z| {
+            if out {
+                // The whole view fits in the box, with its centre on the centre of the box.
+                let zoom = &*z / Float::from((box_w / w).min(box_h / h));
+                *x -= &zoom * Float::from(dx);
+                *y -= &zoom * Float::from(dy);
+                *z = zoom;
+            } else {
+                // The box spans `box_w / w` of the width and `box_h / h` of the height,
+                // so zooming by the larger keeps all of it in view.
+                *x += &*z * Float::from(dx);
+                *y += &*z * Float::from(dy);
+                *z *= Float::from((box_w / w).max(box_h / h));
+            }
+        });
+    }
+}
+
+/// Zoom that is eased toward over the next frames.
+struct ZoomAnimation {
+    target: Float,
+    /// Window position that stays put while zooming.
+    anchor: (f64, f64),
+}
+
+/// Fraction of the remaining zoom covered every frame.
+const ZOOM_EASING: f64 = 0.2;
+
+/// Remaining zoom, in octaves, at which the animation snaps to its target.
+const ZOOM_SNAP: f64 = 1e-3;
+
+/// Pixels the cursor moves while held before a click becomes a box selection.
+const DRAG_THRESHOLD: f64 = 5.0;
+
+/// Factor that `[` and `]` scale `color_scale` by.
+const COLOR_SCALE_STEP: f32 = 1.25;
+
+/// Frames without navigation after which the next navigation is a separate step to undo.
+const HISTORY_IDLE_FRAMES: u32 = 30;
+
+/// Zoom in octaves per line scrolled by the mouse wheel.
+const WHEEL_LINE_ZOOM: f64 = 0.25;
+
+/// Zoom in nats per pixel scrolled by a touchpad.
+const WHEEL_PIXEL_ZOOM: f64 = 0.005;
+
+/// Config of the current view, with its exact position and the size of `render`.
+#[cfg(not(target_arch = "wasm32"))]
+fn view_config(pipeline: &mut Pipeline, render: &Config) -> Config {
+    let mut config = pipeline.read_config(Config::clone);
+    pipeline.read_position(|x, y, z| {
+        config.x = x.to_string();
+        config.y = y.to_string();
+        config.zoom = z.to_string();
+    });
+    config.width = render.width;
+    config.height = render.height;
+    config.ssaa = render.ssaa;
+    config
+}
+
+/// Render `config` to the png at `path` on another thread, so the viewer stays responsive.
+#[cfg(not(target_arch = "wasm32"))]
+fn render_snapshot(config: Config, path: String) {
+    println!(
+        "[VIEWER] Rendering a {}x{} snapshot to {path}",
+        config.width, config.height
+    );
+    std::thread::spawn(move || {
+        let mut pipeline = Pipeline::new(None, config, None);
+        match crate::render_png(&mut pipeline, None, &path, 0) {
+            Ok(()) => println!("[VIEWER] Saved the snapshot to {path}"),
+            Err(err) => eprintln!("[ERROR] Failed to write {path}: {err}"),
+        }
+    });
+}
+
+/// Milliseconds since the unix epoch, naming the files saved by the viewer.
+#[cfg(not(target_arch = "wasm32"))]
+fn timestamp() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis())
+}
+
+/// Text of the overlay, describing the view and the progress of its render.
+fn hud_text(pipeline: &mut Pipeline, iterations: usize) -> String {
+    let to_f64 = |f: &Float| f64::rounding_from(f, RoundingMode::Nearest).0;
+    let (x, y, log2_zoom) =
+        pipeline.read_position(|x, y, z| (to_f64(x), to_f64(y), crate::log2(z)));
+    // Enough decimals to tell pixels apart, as far as an `f64` goes.
+    let decimals = ((-log2_zoom * LOG10_2).ceil() as i64 + 3).clamp(1, 15) as usize;
+    let depth = 1.0 - log2_zoom;
+    format!(
+        "x {x:.decimals$}\n\
+         y {y:.decimals$}\n\
+         zoom {} ({}x, depth {depth:.2})\n\
+         iterations {iterations}\n\
+         remaining {} px\n\
+         orbit {:.1} ms",
+        exp2_sci(log2_zoom),
+        exp2_sci(depth),
+        pipeline.remaining(),
+        pipeline.orbit_time().as_secs_f64() * 1e3,
+    )
+}
+
+/// Cursor, view and progress of the render an inspected pixel was read back at, which
+/// it is only read back again once they change.
+#[derive(PartialEq)]
+struct Inspected {
+    cursor: (f64, f64),
+    position: (Float, Float, Float),
+    remaining: u32,
+}
+
+/// Text of the overlay while inspecting the pixel at `(px, py)`, with the coordinates
+/// cut short to fit.
+fn inspection_text(inspection: &Inspection, px: f64, py: f64) -> String {
+    format!(
+        "pixel {px:.0}, {py:.0}\n\
+         re {:.44}\n\
+         im {:.44}\n\
+         {}",
+        inspection.x.to_string(),
+        inspection.y.to_string(),
+        describe_pixel(inspection),
+    )
+}
+
+/// Iterations of an inspected pixel and the state of its perturbation.
+fn describe_pixel(inspection: &Inspection) -> String {
+    let state = &inspection.state;
+    let iterations = match inspection.smooth {
+        Some(smooth) => format!("j {}, smooth {smooth:.3}", state.j),
+        None if state.finished == 1 => format!("j {}, interior", state.j),
+        None => format!("j {}, rendering", state.j),
+    };
+    let rebased = if state.rebased() { ", rebased" } else { "" };
+    format!("{iterations}\nk {}, q {}{rebased}", state.k, state.q)
+}
+
+/// `2^exponent` in scientific notation, which holds beyond the range of an `f64`.
+fn exp2_sci(exponent: f64) -> String {
+    let log10 = exponent * LOG10_2;
+    let (mut mantissa, mut exponent) = (10f64.powf(log10 - log10.floor()), log10.floor());
+    if mantissa >= 9.995 {
+        mantissa /= 10.0;
+        exponent += 1.0;
+    }
+    format!("{mantissa:.2}e{exponent}")
+}
+
+/// The cursor moved from `from` to `to` far enough to select a box.
+fn is_drag(from: (f64, f64), to: (f64, f64)) -> bool {
+    (from.0 - to.0).abs().max((from.1 - to.1).abs()) >= DRAG_THRESHOLD
+}
+
+/// Value `step` places after `current` in `values`, wrapping around.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: isize) -> T {
+    let index = values.iter().position(|&v| v == current).unwrap_or(0) as isize;
+    values[(index + step).rem_euclid(values.len() as isize) as usize]
+}
+
+/// Bookmark slot of the digit `key`.
+fn bookmark_slot(key: KeyCode) -> Option<u8> {
+    let slot = match key {
+        KeyCode::Digit1 => 1,
+        KeyCode::Digit2 => 2,
+        KeyCode::Digit3 => 3,
+        KeyCode::Digit4 => 4,
+        KeyCode::Digit5 => 5,
+        KeyCode::Digit6 => 6,
+        KeyCode::Digit7 => 7,
+        KeyCode::Digit8 => 8,
+        KeyCode::Digit9 => 9,
+        _ => return None,
+    };
+    Some(slot)
+}
+
+/// Offset of the window position `(px, py)` from the centre of the view in half view
+/// heights, rotated into the complex plane.
+fn plane_offset(
+    window: &glazer::winit::window::Window,
+    rotation: f32,
+    px: f64,
+    py: f64,
+) -> (f64, f64) {
+    let size = window.inner_size();
+    let (w, h) = (size.width as f64, size.height as f64);
+    let sx = (px / w * 2.0 - 1.0) * w / h;
+    let sy = -(py / h * 2.0 - 1.0);
+    rotate(rotation, sx, sy)
+}
+
+/// Rotate the screen offset `(sx, sy)` by `degrees` into the complex plane.
+fn rotate(degrees: f32, sx: f64, sy: f64) -> (f64, f64) {
+    let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+    (sx * cos - sy * sin, sx * sin + sy * cos)
 }
 
 fn handle_input(
@@ -48,23 +398,203 @@ fn handle_input(
                 KeyEvent {
                     physical_key: PhysicalKey::Code(key),
                     state: glazer::winit::event::ElementState::Pressed,
-                    repeat: false,
+                    repeat,
                     ..
                 },
             ..
         }) => match key {
-            KeyCode::KeyP => {
+            KeyCode::KeyP if !repeat => {
                 if let Some(pipeline) = memory.pipeline.as_mut() {
+                    let rotation = pipeline.read_config(|config| config.rotation);
                     pipeline.read_position(|x, y, z| {
                         println!("x = \"{x}\"");
                         println!("y = \"{y}\"");
                         println!("zoom = \"{z}\"");
+                        println!("rotation = {rotation}");
                         println!("iterations = {}\n", memory.config.iterations);
                     });
                 }
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            KeyCode::KeyC if !repeat => {
+                if let Some(pipeline) = memory.pipeline.as_mut() {
+                    let config = view_config(pipeline, &memory.render_config);
+                    let path = format!("view-{}.toml", timestamp());
+                    match crate::config::write_to(&config, &path) {
+                        Ok(()) => println!("[VIEWER] Saved the view to {path}"),
+                        Err(err) => eprintln!("[ERROR] Failed to write {path}: {err}"),
+                    }
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            KeyCode::KeyR if !repeat => {
+                if let Some(pipeline) = memory.pipeline.as_mut() {
+                    let config = view_config(pipeline, &memory.render_config);
+                    render_snapshot(config, format!("snapshot-{}.png", timestamp()));
+                }
+            }
+            KeyCode::KeyH if !repeat => {
+                memory.hud = !memory.hud;
+            }
+            KeyCode::KeyI if !repeat => {
+                memory.inspect = !memory.inspect;
+                memory.inspected = None;
+                let state = if memory.inspect { "on" } else { "off" };
+                println!("[VIEWER] Inspector {state}");
+            }
+            KeyCode::F11 | KeyCode::KeyF if !repeat => {
+                let fullscreen = match window.fullscreen() {
+                    Some(_) => None,
+                    None => Some(Fullscreen::Borderless(None)),
+                };
+                window.set_fullscreen(fullscreen);
+            }
+            KeyCode::Backspace => {
+                let Some(current) = memory.view() else {
+                    return;
+                };
+                let view = if memory.modifiers.shift_key() {
+                    memory.history.redo(current)
+                } else {
+                    memory.history.undo(current)
+                };
+                if let Some(view) = view {
+                    memory.restore(view);
+                }
+            }
+            KeyCode::Digit1
+            | KeyCode::Digit2
+            | KeyCode::Digit3
+            | KeyCode::Digit4
+            | KeyCode::Digit5
+            | KeyCode::Digit6
+            | KeyCode::Digit7
+            | KeyCode::Digit8
+            | KeyCode::Digit9
+                if !repeat =>
+            {
+                let (Some(slot), Some(current)) = (bookmark_slot(key), memory.view()) else {
+                    return;
+                };
+                if memory.modifiers.control_key() || memory.modifiers.super_key() {
+                    memory.bookmarks.set(slot, &current);
+                    println!("[VIEWER] Saved bookmark {slot}");
+                } else if let Some(view) = memory.bookmarks.get(slot) {
+                    memory.history.push(current);
+                    memory.restore(view);
+                } else {
+                    println!("[VIEWER] Bookmark {slot} is empty");
+                }
+            }
+            KeyCode::Tab | KeyCode::KeyM if !repeat => {
+                let step = if memory.modifiers.shift_key() { -1 } else { 1 };
+                if key == KeyCode::Tab {
+                    memory.write_config(|config| {
+                        config.palette = cycle(Preset::ALL, config.palette, step);
+                    });
+                    println!("[VIEWER] palette = {}", memory.config.palette);
+                } else {
+                    memory.write_config(|config| {
+                        config.color_mode = cycle(ColorMode::ALL, config.color_mode, step);
+                    });
+                    println!("[VIEWER] color_mode = {}", memory.config.color_mode);
+                }
+            }
+            KeyCode::BracketLeft | KeyCode::BracketRight => {
+                let factor = match key {
+                    KeyCode::BracketRight => COLOR_SCALE_STEP,
+                    _ => 1.0 / COLOR_SCALE_STEP,
+                };
+                memory.write_config(|config| config.color_scale *= factor);
+                println!("[VIEWER] color_scale = {}", memory.config.color_scale);
+            }
+            KeyCode::Comma | KeyCode::Period => {
+                memory.write_config(|config| {
+                    config.iterations = match key {
+                        KeyCode::Period => (config.iterations * 2).min(MAX_ITERATIONS),
+                        _ => (config.iterations / 2).max(1),
+                    };
+                });
+                println!("[VIEWER] iterations = {}", memory.config.iterations);
+            }
+            KeyCode::KeyO if !repeat => {
+                memory.write_config(|config| config.ssaa = !config.ssaa);
+                println!("[VIEWER] ssaa = {}", memory.config.ssaa);
+            }
+            KeyCode::KeyQ | KeyCode::KeyE if !repeat => {
+                memory.checkpoint();
+                if let Some(pipeline) = memory.pipeline.as_mut() {
+                    let step = if key == KeyCode::KeyQ { 15.0 } else { -15.0 };
+                    let rotation = pipeline.read_config(|config| config.rotation);
+                    pipeline.set_rotation((rotation + step).rem_euclid(360.0));
+                }
+            }
+            KeyCode::ArrowLeft
+            | KeyCode::ArrowRight
+            | KeyCode::ArrowUp
+            | KeyCode::ArrowDown
+            | KeyCode::KeyA
+            | KeyCode::KeyD
+            | KeyCode::KeyW
+            | KeyCode::KeyS => {
+                memory.checkpoint();
+                let Some(pipeline) = memory.pipeline.as_mut() else {
+                    return;
+                };
+                let (sx, sy) = match key {
+                    KeyCode::ArrowLeft | KeyCode::KeyA => (-1.0, 0.0),
+                    KeyCode::ArrowRight | KeyCode::KeyD => (1.0, 0.0),
+                    KeyCode::ArrowUp | KeyCode::KeyW => (0.0, 1.0),
+                    _ => (0.0, -1.0),
+                };
+                // The view is two half heights high.
+                let step = 2.0 * memory.pan_speed;
+                let rotation = pipeline.read_config(|config| config.rotation);
+                let (dx, dy) = rotate(rotation, sx * step, sy * step);
+                pipeline.write_position(|x, y, z| {
+                    *x += &*z * Float::from(dx);
+                    *y += &*z * Float::from(dy);
+                });
+            }
+            KeyCode::Equal | KeyCode::NumpadAdd | KeyCode::Minus | KeyCode::NumpadSubtract => {
+                let factor = match key {
+                    KeyCode::Equal | KeyCode::NumpadAdd => 0.5,
+                    _ => 2.0,
+                };
+                let size = window.inner_size();
+                let (cx, cy) = (size.width as f64 / 2.0, size.height as f64 / 2.0);
+                memory.zoom_toward(factor, cx, cy);
+            }
             _ => {}
         },
+        glazer::Input::Window(WindowEvent::Resized(size)) => {
+            if size.width == 0 || size.height == 0 {
+                return;
+            }
+            memory.config.width = size.width as usize;
+            memory.config.height = size.height as usize;
+            let logical_height = size.height as f64 / window.scale_factor();
+            let Some(pipeline) = memory.pipeline.as_mut() else {
+                memory.logical_height = logical_height;
+                return;
+            };
+
+            // Keep the size of a logical pixel in the plane, so resizing reveals more of
+            // the set rather than stretching it, and a change of scale factor keeps the
+            // framing.
+            if memory.logical_height > 0.0 && logical_height != memory.logical_height {
+                let scale = Float::from(logical_height / memory.logical_height);
+                pipeline.write_position(|_, _, z| *z *= &scale);
+                if let Some(zoom) = memory.zoom.as_mut() {
+                    zoom.target *= scale;
+                }
+            }
+            memory.logical_height = logical_height;
+            pipeline.resize(size.width as usize, size.height as usize);
+        }
+        glazer::Input::Window(WindowEvent::ModifiersChanged(modifiers)) => {
+            memory.modifiers = modifiers.state();
+        }
         glazer::Input::Window(WindowEvent::CursorMoved { position, .. }) => {
             memory.cursor_x = position.x;
             memory.cursor_y = position.y;
@@ -72,67 +602,116 @@ fn handle_input(
             // memory.cursor_y /= scale;
             // memory.cursor_x /= scale;
         }
-        glazer::Input::Window(WindowEvent::MouseInput {
-            state: ElementState::Pressed,
-            button,
-            ..
-        }) => {
+        glazer::Input::Window(WindowEvent::MouseInput { state, button, .. }) => {
+            let cursor = (memory.cursor_x, memory.cursor_y);
+            match (button, state) {
+                (MouseButton::Left, ElementState::Pressed) if memory.inspect => {
+                    if let Some(pipeline) = &memory.pipeline {
+                        let inspection = pipeline.inspect(cursor.0, cursor.1);
+                        println!("[VIEWER] Pixel {:.0}, {:.0}", cursor.0, cursor.1);
+                        println!("x = \"{}\"", inspection.x);
+                        println!("y = \"{}\"", inspection.y);
+                        println!("{}", describe_pixel(&inspection));
+                    }
+                }
+                (MouseButton::Left, ElementState::Pressed) => memory.drag = Some(cursor),
+                (MouseButton::Left, ElementState::Released) => {
+                    let Some(start) = memory.drag.take() else {
+                        return;
+                    };
+                    if is_drag(start, cursor) {
+                        let out = memory.modifiers.shift_key();
+                        memory.zoom_to_box(window, start, cursor, out);
+                    } else {
+                        memory.zoom_toward(0.5, cursor.0, cursor.1);
+                    }
+                }
+                (MouseButton::Right, ElementState::Pressed) => {
+                    memory.zoom_toward(2.0, cursor.0, cursor.1);
+                }
+                _ => {}
+            }
+        }
+        glazer::Input::Device(DeviceEvent::MouseWheel { delta }) => {
+            let factor = match delta {
+                MouseScrollDelta::PixelDelta(delta) => (-delta.y * WHEEL_PIXEL_ZOOM).exp(),
+                MouseScrollDelta::LineDelta(_, lines) => (-lines as f64 * WHEEL_LINE_ZOOM).exp2(),
+            };
+            memory.zoom_toward(factor, memory.cursor_x, memory.cursor_y);
+        }
+        glazer::Input::Window(WindowEvent::PinchGesture { delta, .. }) => {
+            // A positive delta spreads the fingers apart, magnifying the view.
+            let factor = (-delta).exp();
+            memory.zoom_toward(factor, memory.cursor_x, memory.cursor_y);
+        }
+        glazer::Input::Window(WindowEvent::PanGesture { delta, .. }) => {
+            memory.checkpoint();
             let Some(pipeline) = memory.pipeline.as_mut() else {
                 return;
             };
-
-            let physical_size = window.inner_size();
-            let w = Float::from(physical_size.width);
-            let h = Float::from(physical_size.height);
-
+            // The view follows the fingers, so the centre moves against them.
+            let size = window.inner_size();
+            let (cx, cy) = (size.width as f64 / 2.0, size.height as f64 / 2.0);
+            let rotation = pipeline.read_config(|config| config.rotation);
+            let (dx, dy) = plane_offset(window, rotation, cx - delta.x as f64, cy - delta.y as f64);
             pipeline.write_position(|x, y, z| {
-                let factor = match button {
-                    MouseButton::Left => Float::ONE_HALF,
-                    MouseButton::Right => Float::TWO,
-                    _ => return,
-                };
-                let zs = match button {
-                    MouseButton::Left => Float::ONE,
-                    MouseButton::Right => Float::NEGATIVE_ONE,
-                    _ => return,
-                };
+                *x += &*z * Float::from(dx);
+                *y += &*z * Float::from(dy);
+            });
+        }
+        _ => {}
+    }
+}
 
-                let cx = Float::from(memory.cursor_x);
-                let cy = Float::from(memory.cursor_y);
-                let one = Float::ONE;
-                let two = Float::TWO;
+/// Ease the zoom toward its target, keeping the anchor of the animation in place.
+fn animate_zoom(window: &glazer::winit::window::Window, memory: &mut Memory) {
+    let (Some(pipeline), Some(zoom)) = (memory.pipeline.as_mut(), memory.zoom.as_ref()) else {
+        return;
+    };
 
-                let dx = (&cx / &w * &two - &one) * w / &h * &zs;
-                let dy = (&cy / &h * &two - &one) * zs * Float::NEGATIVE_ONE;
+    let current = pipeline.read_position(|_, _, z| z.clone());
+    let remaining = crate::log2(&zoom.target) - crate::log2(&current);
+    let next = if remaining.abs() < ZOOM_SNAP {
+        zoom.target.clone()
+    } else {
+        // Only the final snap needs to be exact, so each step is a power of two
+        // shift and a scale by the fractional octave.
+        let octaves = remaining * ZOOM_EASING;
+        let whole = octaves.floor();
+        (&current << whole as i64) * Float::from((octaves - whole).exp2())
+    };
 
-                *x += &*z * &dx;
-                *y += &*z * &dy;
-                *z *= factor;
-            });
-        }
-        glazer::Input::Device(DeviceEvent::MouseWheel { delta }) => match delta {
-            MouseScrollDelta::PixelDelta(delta) => {
-                let Some(pipeline) = memory.pipeline.as_mut() else {
-                    return;
-                };
+    // The point under the anchor is `c + z * d`, so it stays put when the centre
+    // moves by `(z - z') * d`.
+    let rotation = pipeline.read_config(|config| config.rotation);
+    let (dx, dy) = plane_offset(window, rotation, zoom.anchor.0, zoom.anchor.1);
+    pipeline.write_position(|x, y, z| {
+        let shift = &*z - &next;
+        *x += &shift * Float::from(dx);
+        *y += shift * Float::from(dy);
+        *z = next;
+    });
 
-                pipeline.write_position(|_, _, z| {
-                    let sensitivity = 0.005;
-                    *z *= Float::from((-delta.y * sensitivity).exp());
-                });
-            }
-            _ => unimplemented!(),
-        },
-        _ => {}
+    if remaining.abs() < ZOOM_SNAP {
+        memory.zoom = None;
     }
 }
 
 fn update_and_render(
     glazer::PlatformUpdate { window, memory, .. }: glazer::PlatformUpdate<Memory>,
 ) {
-    window.set_resizable(false);
+    window.set_resizable(true);
     window.set_title("Mandelbrot Set");
 
+    if memory.pipeline.is_none() {
+        let size = window.inner_size();
+        if size.width != 0 && size.height != 0 {
+            memory.config.width = size.width as usize;
+            memory.config.height = size.height as usize;
+            memory.logical_height = size.height as f64 / window.scale_factor();
+        }
+    }
+
     #[cfg(target_arch = "wasm32")]
     {
         if memory.pipeline.is_none() {
@@ -151,7 +730,47 @@ fn update_and_render(
         .pipeline
         .get_or_insert_with(|| Pipeline::new(Some(window), memory.config.clone(), None));
 
+    animate_zoom(window, memory);
+    if memory.zoom.is_none() {
+        memory.idle_frames = memory.idle_frames.saturating_add(1);
+    }
     if let Some(pipeline) = &mut memory.pipeline {
+        let cursor = (memory.cursor_x, memory.cursor_y);
+        let text = memory
+            .hud
+            .then(|| hud_text(pipeline, memory.config.iterations));
+        let inspection = if memory.inspect {
+            // Reading the pixel back waits for the GPU, so it is only done again once
+            // the cursor moves or the render advances.
+            let key = Inspected {
+                cursor,
+                position: pipeline.read_position(|x, y, z| (x.clone(), y.clone(), z.clone())),
+                remaining: pipeline.remaining(),
+            };
+            if memory
+                .inspected
+                .as_ref()
+                .is_none_or(|(inspected, _)| *inspected != key)
+            {
+                let inspection = pipeline.inspect(cursor.0, cursor.1);
+                let text = inspection_text(&inspection, cursor.0, cursor.1);
+                memory.inspected = Some((key, text));
+            }
+            memory.inspected.as_ref().map(|(_, text)| text.clone())
+        } else {
+            None
+        };
+        let text = match (text, inspection) {
+            (Some(text), Some(inspection)) => Some(format!("{text}\n{inspection}")),
+            (text, inspection) => text.or(inspection),
+        };
+        let scale = 2 * window.scale_factor().round().max(1.0) as u32;
+        pipeline.set_hud(text.as_deref(), scale);
+        let selection = memory
+            .drag
+            .filter(|&start| is_drag(start, cursor))
+            .map(|start| (start, cursor));
+        pipeline.set_selection(selection);
         pipeline.force_step_mandelbrot(memory.config.iterations);
     }
 }